@@ -1,262 +1,15 @@
-use itertools::Itertools;
 use tokio::prelude::*;
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc;
 use futures::future::join_all;
-use tokio_test::block_on;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
 
-type Numeric = i64;
+use intcode::{str_to_vec, ChannelComp, Debugger, Event, IntcodeComp, IntcodeError, Numeric, IO};
 
-struct IO {
-    tx: Option<mpsc::Sender<Numeric>>, // send on this channel
-    tx_signal: Option<mpsc::Receiver<bool>>, // request sends come in here
-    rx: Option<mpsc::Receiver<Numeric>>, // receive on this channel
-    rx_signal: Option<mpsc::Sender<bool>>, // request sends originate here
-    history: Vec<Numeric>, // history of what was sent
-}
-
-impl IO {
-    fn new() -> IO {
-        let (s, r) = mpsc::channel(100);
-        IO{tx: Some(s), tx_signal: None, rx: Some(r), rx_signal: None, history: Vec::new()}
-    }
-
-    async fn from(input: Numeric) -> IO {
-        let (s, r) = mpsc::channel(100);
-        let mut io = IO{tx: Some(s), tx_signal: None, rx: Some(r), rx_signal: None, history: Vec::new()};
-        io.send_output(input).await;
-        io
-    }
-
-    fn get_history_last(&self) -> Option<Numeric> {
-        match self.history.last() {
-            Some(x) => Some(*x),
-            None => None,
-        }
-    }
-
-    async fn get_input(&mut self) -> Numeric {
-        match &mut self.rx_signal {
-            Some(t) => match t.send(true).await {
-                Ok(_) => (),
-                Err(_) => { println!("WARN: get_input failed"); },
-            },
-            None => (),
-        };
-        match &mut self.rx {
-            Some(r) => match r.recv().await {
-                Some(x) => x,
-                None => panic!("no more input available"),
-            },
-            None => panic!("input channel not available"),
-        }
-    }
-
-    async fn await_send_output(&mut self) -> Option<()> {
-        match &mut self.tx_signal {
-            Some(r) => match r.recv().await {
-                Some(_) => Some(()),
-                None => None,
-            },
-            None => None,
-        }
-    }
-
-    async fn send_output(&mut self, out: Numeric) -> () {
-        self.history.push(out);
-        match &mut self.tx {
-            Some(t) => match t.send(out).await {
-                Ok(_) => (),
-                Err(_) => { println!("WARN: send_output failed"); },
-            },
-            None => panic!("output channel not available"),
-        }
-    }
-}
-
-struct IntcodeComp {
-    name: usize, // name of computer
-    instrs: Vec<Numeric>, // instructions / memory
-    io: IO, // io
-    ptr: usize, // current instruction pointer
-    rel_base: Numeric, // relative base for mode 3
-}
-
-impl IntcodeComp {
-    fn new(name: usize, instrs: Vec<Numeric>, io: IO) -> IntcodeComp {
-        IntcodeComp{name: name, instrs: instrs, io: io, ptr: 0, rel_base: 0}
-    }
-
-    fn get_modes(instr: Numeric) -> (Numeric,Numeric,Numeric) {
-        (instr % 1000 / 100,
-         instr % 10000 / 1000,
-         instr % 100000 / 10000,
-        )
-    }
-
-    async fn run(&mut self) -> Option<Numeric> {
-        let name = self.name.clone();
-        while self.ptr < self.instrs.len() {
-            let instr = self.instrs[self.ptr];
-            let modes = IntcodeComp::get_modes(instr);
-            println!("{}: instr pointer = {}, mode = {:?}", self.name, self.ptr, modes);
-            println!("\t instrs: {:?}", self.instrs.get(self.ptr..self.ptr+4));
-            match instr%100 {
-                1 => { // add
-                    self.ptr = self.three_instr(modes, |x,y| {
-                        println!("{}: adding {} + {}", name, x, y);
-                        x + y
-                    });
-                },
-                2 => { // multiply
-                    self.ptr = self.three_instr(modes, |x,y| {
-                        println!("{}: multiplying {} * {}", name, x, y);
-                        x * y
-                    });
-                },
-                3 => { // input
-                    println!("{}: getting input", name);
-                    let val = self.io.get_input().await;
-                    self.store(self.ptr+1, modes.0, val);
-                    self.ptr += 2;
-                },
-                4 => { // output
-                    let val = self.load(self.ptr+1, modes.0);
-                    println!("{}: sending output = {}", name, val);
-                    self.io.send_output(val).await;
-                    self.ptr += 2;
-                },
-                5 => { // jump-if-true
-                    self.ptr = self.jump_instr(modes, |x,y| {
-                        match x != 0 {
-                            true => {
-                                println!("{}: jump-if-true {}", name, y);
-                                assert!(y >= 0);
-                                Some(y)
-                            },
-                            false => None
-                        }
-                    });
-                },
-                6 => { // jump-if-false
-                    self.ptr = self.jump_instr(modes, |x,y| {
-                        match x == 0 {
-                            true => {
-                                println!("{}: jump-if-false {}", name, y);
-                                assert!(y >= 0);
-                                Some(y)
-                            },
-                            false => None
-                        }
-                    });
-                },
-                7 => { // less-than
-                    self.ptr = self.three_instr(modes, |x,y| {
-                        println!("{}: less-than {} < {}", name, x, y);
-                        match x < y { true => 1, false => 0 }
-                    });
-                },
-                8 => { // equals
-                    self.ptr = self.three_instr(modes, |x,y| {
-                        println!("{}: equals {} == {}", name, x, y);
-                        match x == y { true => 1, false => 0 }
-                    });
-                },
-                9 => { // set relative base
-                    self.rel_base += self.load(self.ptr+1, modes.0);
-                    self.ptr += 2;
-                },
-                99 => break,
-                _ => panic!("{}: unknown instr: {}", name, instr),
-            };
-        }
-        if self.ptr >= self.instrs.len() {
-            panic!("hit end")
-        }
-        println!("finishing {}", name);
-        self.io.get_history_last()
-    }
-    
-    fn set_mem(&mut self, index: usize, val: Numeric) -> () {
-        if index >= self.instrs.len() {
-            self.instrs.resize(index+1, 0);
-        }
-        self.instrs[index] = val;
-    }
-
-    fn get_mem(&mut self, index: usize) -> Numeric {
-        if index >= self.instrs.len() {
-            self.instrs.resize(index+1, 0);
-        }
-        self.instrs[index]        
-    }
-
-    fn load(&mut self, index: usize, mode: Numeric) -> Numeric {
-        match mode {
-            0 => {
-                let pos = self.get_mem(index);
-                assert!(pos >= 0);
-                self.get_mem(pos as usize)
-            },
-            1 => self.get_mem(index),
-            2 => {
-                let pos = self.rel_base+self.get_mem(index);
-                assert!(pos >= 0);
-                self.get_mem(pos as usize)
-            },
-            x => panic!("bad mode {}", x),
-        }
-    }
-
-    fn store(&mut self, index: usize, mode: Numeric, val: Numeric) -> () {
-        match mode {
-            0 => {
-                let pos = self.get_mem(index);
-                assert!(pos >= 0);
-                self.set_mem(pos as usize, val);
-                println!("\t [{}] = {}", pos, self.instrs[pos as usize]);
-            },
-            1 => panic!("store cannot be immediate"),
-            2 => {
-                let pos = self.rel_base+self.get_mem(index);
-                assert!(pos >= 0);
-                self.set_mem(pos as usize, val);
-                println!("\t [{}] = {}", pos, self.instrs[pos as usize]);
-            },
-            x => panic!("bad mode {}", x),
-        }
-    }
-
-    fn three_instr<F>(&mut self, modes: (Numeric,Numeric,Numeric), op: F) -> usize where
-            F: Fn(Numeric, Numeric) -> Numeric {
-        let val1 = self.load(self.ptr+1, modes.0);
-        let val2 = self.load(self.ptr+2, modes.1);
-        self.store(self.ptr+3, modes.2, op(val1, val2));
-        self.ptr + 4
-    }
-
-    fn jump_instr<F>(&mut self, modes: (Numeric,Numeric,Numeric), op: F) -> usize where
-            F: Fn(Numeric, Numeric) -> Option<Numeric> {
-        let val1 = self.load(self.ptr+1, modes.0);
-        let val2 = self.load(self.ptr+2, modes.1);
-        match op(val1, val2) {
-            Some(x) => x as usize,
-            None => self.ptr + 3
-        }
-    }
-}
-
-fn str_to_vec(input: &str) -> Vec<Numeric> {
-
-    let mut instrs = Vec::new();
-    for e in input.split(",") {
-        instrs.push(e.parse::<Numeric>().unwrap());
-    }
-    instrs
-}
-
-async fn get_thrust_impl(instrs: &Vec<Numeric>, combinations: &Vec<Numeric>) -> Option<Numeric> {
+/// Run an amplifier chain; `debugger` gives the debugger, if any, to attach
+/// to each machine.
+async fn get_thrust_impl(instrs: &Vec<Numeric>, combinations: &Vec<Numeric>,
+                         debugger: fn(usize) -> Option<Debugger>) -> Option<Numeric> {
     let mut program_vec = Vec::new();
     for _ in combinations {
         program_vec.push(instrs.clone());
@@ -287,7 +40,11 @@ async fn get_thrust_impl(instrs: &Vec<Numeric>, combinations: &Vec<Numeric>) ->
 
     let mut computers = Vec::new();
     for (name, (prog, io)) in names.zip(program_vec.drain(..).zip(io.drain(..))) {
-        computers.push(IntcodeComp::new(name, prog, io));
+        let mut comp = ChannelComp::new(name, prog, io);
+        if let Some(d) = debugger(name) {
+            comp = comp.with_debugger(d);
+        }
+        computers.push(comp);
     }
     let mut fut = Vec::new();
     for c in computers.iter_mut() {
@@ -296,165 +53,148 @@ async fn get_thrust_impl(instrs: &Vec<Numeric>, combinations: &Vec<Numeric>) ->
     // run and return the last program's result
     let val = join_all(fut).await;
     println!("programs finished");
-    val[combinations.len()-1]
+    for e in val.iter().filter_map(|v| v.err()) {
+        println!("{}", e);
+    }
+    val[combinations.len()-1].unwrap_or(None)
 }
 
 fn get_thrust(instrs: &Vec<Numeric>, combinations: &Vec<Numeric>) -> Option<Numeric> {
     let mut pool = Runtime::new().unwrap();
-    pool.block_on(get_thrust_impl(instrs, combinations))
+    pool.block_on(get_thrust_impl(instrs, combinations, Debugger::from_env))
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Colour {
+    Black = 0,
+    White = 1,
 }
 
-type Drawing = Vec<Vec<char>>;
+impl Colour {
+    fn from_i64(value: Numeric) -> Colour {
+        match value {
+            0 => Colour::Black,
+            1 => Colour::White,
+            x => panic!("bad color: {}", x),
+        }
+    }
+}
+
+type Pos = (Numeric,Numeric); // x,y with y growing downwards
+
+/// Colour of each panel; anything not in the map is black.
+type Hull = HashMap<Pos, Colour>;
 
-struct Painter {
-    panels: Drawing,
-    center_pos: (Numeric,Numeric), // col,row
-    cur_pos: (Numeric,Numeric), // col,row
-    io: IO,
+struct Robot {
+    comp: IntcodeComp,
+    hull: Hull,
+    painted: HashSet<Pos>, // panels painted at least once
+    pos: Pos,
+    dir: Pos, // unit step the robot is facing
 }
 
-impl Painter {
-    fn new(io: IO) -> Painter {
-        Painter{
-            panels: vec![vec!['.']],
-            center_pos: (0,0),
-            cur_pos: (0,0),
-            io: io,
+impl Robot {
+    fn new(instrs: &Vec<Numeric>, start: Colour) -> Robot {
+        let mut hull = HashMap::new();
+        if start == Colour::White {
+            hull.insert((0,0), start);
+        }
+        Robot{
+            comp: IntcodeComp::new(0, instrs.clone()),
+            hull: hull,
+            painted: HashSet::new(),
+            pos: (0,0),
+            dir: (0,-1), // start pointing up
         }
     }
 
-    async fn camera(&mut self) -> () {
-        loop {
-            match self.io.await_send_output().await {
-                Some(_) => { // send back the current panel
-                    let color_int = match self.panels[self.cur_pos.1 as usize][self.cur_pos.0 as usize] {
-                        '.' => 0,
-                        '#' => 1,
-                        x => panic!("strange color: {}", x),
-                    };
-                    self.io.send_output(color_int).await;
-                },
-                None => return // painting must be done
-            };
-        }           
+    fn colour(&self, pos: Pos) -> Colour {
+        *self.hull.get(&pos).unwrap_or(&Colour::Black)
     }
 
-    async fn run(&mut self) -> () {
-        let mut last_dir = (1,0); // start pointing up
-        let mut movement = false;
+    fn turn(&mut self, instr: Numeric) {
+        let (dx, dy) = self.dir;
+        self.dir = match instr {
+            0 => (dy, -dx), // left 90 degrees
+            1 => (-dy, dx), // right 90 degrees
+            x => panic!("bad direction: {}", x),
+        };
+        self.pos = (self.pos.0 + self.dir.0, self.pos.1 + self.dir.1);
+    }
+
+    /// Run the brain until it halts: it reads the colour under the robot
+    /// and answers with a colour to paint and a direction to turn.
+    fn run(&mut self) -> Result<(), IntcodeError> {
+        let mut painted = false;
         loop {
-            let instr = self.io.get_input().await;
-            if movement {
-                last_dir = match instr {
-                    0 => { // left 90 degrees
-                        match last_dir {
-                            (1,0) => (0,-1),
-                            (0,-1) => (-1,0),
-                            (-1,0) => (0,1),
-                            (0,1) => (1,0),
-                            _ => panic!("bad last_dir"),
-                        }
-                    },
-                    1 => { // right 90 degrees
-                        match last_dir {
-                            (1,0) => (0,1),
-                            (0,1) => (-1,0),
-                            (-1,0) => (0,-1),
-                            (0,-1) => (1,0),
-                            _ => panic!("bad last_dir"),
-                        }
-                    },
-                    x => panic!("bad direction: {}", x),
-                };
-                self.cur_pos = (self.cur_pos.0 + last_dir.0, self.cur_pos.1 + last_dir.1);
-                if self.cur_pos.0 < 0 {
-                    self.shift_right();
-                } else if self.cur_pos.1 < 0 {
-                    self.shift_up();
-                } else if self.cur_pos.0 >= self.panels.len() as Numeric {
-                    self.add_x();
-                } else if self.cur_pos.1 >= self.panels[0].len() as Numeric {
-                    self.add_y();
-                }
-                movement = false;
-            } else {
-                let color = match instr {
-                    0 => '#',
-                    1 => '.',
-                    e => panic!("bad color: {}", e),
-                };
-                self.panels[self.cur_pos.1 as usize][self.cur_pos.0 as usize] = color;
+            match self.comp.run_until_event()? {
+                Event::NeedsInput => {
+                    let colour = self.colour(self.pos);
+                    self.comp.push_input(colour as Numeric);
+                },
+                Event::Output(instr) if !painted => {
+                    self.hull.insert(self.pos, Colour::from_i64(instr));
+                    self.painted.insert(self.pos);
+                    painted = true;
+                },
+                Event::Output(instr) => {
+                    self.turn(instr);
+                    painted = false;
+                },
+                Event::Halted => return Ok(()),
             }
         }
     }
 
-    fn shift_up(&mut self) -> () { // move center_pos +1 y
-        self.panels.insert(0, vec![' '; self.panels[0].len()]);
-        self.center_pos = (self.center_pos.0, self.center_pos.1+1);
-        self.cur_pos = (self.cur_pos.0, self.cur_pos.1+1);
+    /// Panels painted at least once, whatever colour they ended up.
+    fn painted(&self) -> usize {
+        self.painted.len()
     }
-    fn shift_right(&mut self) -> () { // move center_pos +1 x
-        for row in self.panels.iter_mut() {
-            row.insert(0, '.');
+
+    fn render(&self) -> String {
+        let white: Vec<&Pos> = self.hull.iter().filter(|(_, c)| **c == Colour::White).map(|(p, _)| p).collect();
+        if white.is_empty() {
+            return String::new();
         }
-        self.center_pos = (self.center_pos.0+1, self.center_pos.1);
-        self.cur_pos = (self.cur_pos.0+1, self.cur_pos.1);
-    }
-    fn add_y(&mut self) -> () { // add a row on the end
-        self.panels.push(vec!['.'; self.panels[0].len()]);
-    }
-    fn add_x(&mut self) -> () { // add a col on the end
-        for row in self.panels.iter_mut() {
-            row.push('.');
+        let (min_x, max_x) = (white.iter().map(|p| p.0).min().unwrap(), white.iter().map(|p| p.0).max().unwrap());
+        let (min_y, max_y) = (white.iter().map(|p| p.1).min().unwrap(), white.iter().map(|p| p.1).max().unwrap());
+        let mut screen = String::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                screen.push(match self.colour((x,y)) {
+                    Colour::White => '#',
+                    Colour::Black => '.',
+                });
+            }
+            screen.push('\n');
         }
+        screen
     }
-
 }
 
-async fn painting_drone(instrs: &Vec<Numeric>) -> Drawing {
-    
-    let (s1, r1) = mpsc::channel(100);
-    let (s2, r2) = mpsc::channel(100);
-    let (s3, r3) = mpsc::channel(100);
-    let (s4, r4) = mpsc::channel(100);
-    
-    let drone_io = IO{tx: Some(s3), tx_signal: Some(r2), rx: Some(r1),
-                      rx_signal: Some(s4), history: Vec::new()};
-    let paint_io = IO{tx: Some(s1), tx_signal: Some(r4), rx: Some(r3),
-                      rx_signal: Some(s2), history: Vec::new()};
+const BRAIN: &str = "3,8,1005,8,319,1106,0,11,0,0,0,104,1,104,0,3,8,102,-1,8,10,1001,10,1,10,4,10,108,1,8,10,4,10,101,0,8,28,2,1105,12,10,1006,0,12,3,8,102,-1,8,10,101,1,10,10,4,10,1008,8,0,10,4,10,102,1,8,58,2,107,7,10,1006,0,38,2,1008,3,10,3,8,1002,8,-1,10,1001,10,1,10,4,10,108,0,8,10,4,10,1001,8,0,90,3,8,1002,8,-1,10,101,1,10,10,4,10,108,0,8,10,4,10,101,0,8,112,1006,0,65,1,1103,1,10,1006,0,91,3,8,102,-1,8,10,101,1,10,10,4,10,108,1,8,10,4,10,101,0,8,144,1006,0,32,3,8,1002,8,-1,10,101,1,10,10,4,10,108,1,8,10,4,10,102,1,8,169,1,109,12,10,1006,0,96,1006,0,5,3,8,102,-1,8,10,1001,10,1,10,4,10,108,1,8,10,4,10,101,0,8,201,3,8,102,-1,8,10,1001,10,1,10,4,10,108,0,8,10,4,10,1001,8,0,223,1,4,9,10,2,8,5,10,1,3,4,10,3,8,1002,8,-1,10,1001,10,1,10,4,10,108,1,8,10,4,10,101,0,8,257,1,1,9,10,1006,0,87,3,8,102,-1,8,10,1001,10,1,10,4,10,1008,8,0,10,4,10,102,1,8,287,2,1105,20,10,1,1006,3,10,1,3,4,10,101,1,9,9,1007,9,1002,10,1005,10,15,99,109,641,104,0,104,1,21102,1,932972962600,1,21101,0,336,0,1106,0,440,21101,838483681940,0,1,21101,0,347,0,1106,0,440,3,10,104,0,104,1,3,10,104,0,104,0,3,10,104,0,104,1,3,10,104,0,104,1,3,10,104,0,104,0,3,10,104,0,104,1,21101,3375393987,0,1,21101,394,0,0,1105,1,440,21102,46174071847,1,1,21102,1,405,0,1106,0,440,3,10,104,0,104,0,3,10,104,0,104,0,21101,988648461076,0,1,21101,428,0,0,1106,0,440,21101,0,709580452200,1,21101,439,0,0,1105,1,440,99,109,2,22101,0,-1,1,21101,40,0,2,21102,1,471,3,21102,461,1,0,1106,0,504,109,-2,2106,0,0,0,1,0,0,1,109,2,3,10,204,-1,1001,466,467,482,4,0,1001,466,1,466,108,4,466,10,1006,10,498,1102,0,1,466,109,-2,2105,1,0,0,109,4,1202,-1,1,503,1207,-3,0,10,1006,10,521,21102,1,0,-3,22102,1,-3,1,21201,-2,0,2,21101,0,1,3,21102,540,1,0,1106,0,545,109,-4,2106,0,0,109,5,1207,-3,1,10,1006,10,568,2207,-4,-2,10,1006,10,568,22101,0,-4,-4,1105,1,636,22102,1,-4,1,21201,-3,-1,2,21202,-2,2,3,21102,1,587,0,1105,1,545,22101,0,1,-4,21102,1,1,-1,2207,-4,-2,10,1006,10,606,21101,0,0,-1,22202,-2,-1,-2,2107,0,-3,10,1006,10,628,21201,-1,0,1,21101,0,628,0,106,0,503,21202,-2,-1,-2,22201,-4,-2,-4,109,-5,2106,0,0";
 
-    let mut drone = IntcodeComp::new(0, instrs.clone(), drone_io);
-    let paint = Arc::new(Painter::new(paint_io));
-
-    // set up the painter
-    let mut p_r = Arc::clone(&paint);
-    tokio::spawn(async move { Arc::get_mut(&mut p_r).unwrap().run().await; });
-    let mut p_c = Arc::clone(&paint);
-    tokio::spawn(async move { Arc::get_mut(&mut p_c).unwrap().camera().await; });
-
-    // run the drone
-    drone.run().await;
-    println!("drone finished");
-    paint.panels.clone()
-}
+fn main() -> () {
+    let v = str_to_vec(BRAIN);
+    let mut robot = Robot::new(&v, Colour::Black);
+    if let Err(e) = robot.run() {
+        println!("{}", e);
+    }
+    println!("Panels painted: {}", robot.painted());
 
-#[tokio::main]
-async fn main() -> () {
-    let input = "3,8,1005,8,319,1106,0,11,0,0,0,104,1,104,0,3,8,102,-1,8,10,1001,10,1,10,4,10,108,1,8,10,4,10,101,0,8,28,2,1105,12,10,1006,0,12,3,8,102,-1,8,10,101,1,10,10,4,10,1008,8,0,10,4,10,102,1,8,58,2,107,7,10,1006,0,38,2,1008,3,10,3,8,1002,8,-1,10,1001,10,1,10,4,10,108,0,8,10,4,10,1001,8,0,90,3,8,1002,8,-1,10,101,1,10,10,4,10,108,0,8,10,4,10,101,0,8,112,1006,0,65,1,1103,1,10,1006,0,91,3,8,102,-1,8,10,101,1,10,10,4,10,108,1,8,10,4,10,101,0,8,144,1006,0,32,3,8,1002,8,-1,10,101,1,10,10,4,10,108,1,8,10,4,10,102,1,8,169,1,109,12,10,1006,0,96,1006,0,5,3,8,102,-1,8,10,1001,10,1,10,4,10,108,1,8,10,4,10,101,0,8,201,3,8,102,-1,8,10,1001,10,1,10,4,10,108,0,8,10,4,10,1001,8,0,223,1,4,9,10,2,8,5,10,1,3,4,10,3,8,1002,8,-1,10,1001,10,1,10,4,10,108,1,8,10,4,10,101,0,8,257,1,1,9,10,1006,0,87,3,8,102,-1,8,10,1001,10,1,10,4,10,1008,8,0,10,4,10,102,1,8,287,2,1105,20,10,1,1006,3,10,1,3,4,10,101,1,9,9,1007,9,1002,10,1005,10,15,99,109,641,104,0,104,1,21102,1,932972962600,1,21101,0,336,0,1106,0,440,21101,838483681940,0,1,21101,0,347,0,1106,0,440,3,10,104,0,104,1,3,10,104,0,104,0,3,10,104,0,104,1,3,10,104,0,104,1,3,10,104,0,104,0,3,10,104,0,104,1,21101,3375393987,0,1,21101,394,0,0,1105,1,440,21102,46174071847,1,1,21102,1,405,0,1106,0,440,3,10,104,0,104,0,3,10,104,0,104,0,21101,988648461076,0,1,21101,428,0,0,1106,0,440,21101,0,709580452200,1,21101,439,0,0,1105,1,440,99,109,2,22101,0,-1,1,21101,40,0,2,21102,1,471,3,21102,461,1,0,1106,0,504,109,-2,2106,0,0,0,1,0,0,1,109,2,3,10,204,-1,1001,466,467,482,4,0,1001,466,1,466,108,4,466,10,1006,10,498,1102,0,1,466,109,-2,2105,1,0,0,109,4,1202,-1,1,503,1207,-3,0,10,1006,10,521,21102,1,0,-3,22102,1,-3,1,21201,-2,0,2,21101,0,1,3,21102,540,1,0,1106,0,545,109,-4,2106,0,0,109,5,1207,-3,1,10,1006,10,568,2207,-4,-2,10,1006,10,568,22101,0,-4,-4,1105,1,636,22102,1,-4,1,21201,-3,-1,2,21202,-2,2,3,21102,1,587,0,1105,1,545,22101,0,1,-4,21102,1,1,-1,2207,-4,-2,10,1006,10,606,21101,0,0,-1,22202,-2,-1,-2,2107,0,-3,10,1006,10,628,21201,-1,0,1,21101,0,628,0,106,0,503,21202,-2,-1,-2,22201,-4,-2,-4,109,-5,2106,0,0";
-    let v = str_to_vec(input);
-    let drawing = painting_drone(&v).await;
-    println!("Hull looks like:");
-    for row in drawing {
-        for c in row {
-            print!("{}",c);
-        }
-        println!("");
+    let mut robot = Robot::new(&v, Colour::White);
+    if let Err(e) = robot.run() {
+        println!("{}", e);
     }
+    println!("Hull looks like:");
+    print!("{}", robot.render());
 }
 
-
+#[cfg(test)]
 mod tests {
     use super::*;
+    use intcode::network::Topology;
+    use intcode::phase;
 
     #[test]
     fn test_get_thrust() {
@@ -483,228 +223,47 @@ mod tests {
         assert_eq!(t, Some(65210));
     }
 
-    #[test]
-    fn test_str_to_vec() {
-        let input = "1,2,3,4";
-        assert_eq!(str_to_vec(input), vec![1,2,3,4]);
-    }
-
-    #[test]
-    fn test_get_modes() {
-        assert_eq!(IntcodeComp::get_modes(1002),(0,1,0));
-    }
-
-    #[test]
-    fn test_set_mem() {
-        let v = vec![1,2,3,4];
-        let io = IO::new();
-        let mut comp = IntcodeComp::new(0, v, io);
-        comp.set_mem(100, 10);
-        assert_eq!(comp.instrs[100], 10);
-        assert_eq!(comp.instrs[99], 0);
-    }
-
-    #[test]
-    fn test_run_day4() {
-        async fn t() {
-            let input = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
-            let v = str_to_vec(input);
-            let io = IO::from(7).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 999),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day4a() {
-        async fn t() {
-            let input = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
-            let v = str_to_vec(input);
-            let io = IO::from(8).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 1000),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day4b() {
-        async fn t() {
-            let input = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
-            let v = str_to_vec(input);
-            let io = IO::from(9).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 1001),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day5_1a() {
-        async fn t() {
-            let input = "3,9,8,9,10,9,4,9,99,-1,8";
-            let v = str_to_vec(input);
-            let io = IO::from(8).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 1),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day5_1b() {
-        async fn t() {
-            let input = "3,9,8,9,10,9,4,9,99,-1,8";
-            let v = str_to_vec(input);
-            let io = IO::from(16).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 0),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day5_2a() {
-        async fn t() {
-            let input = "3,9,7,9,10,9,4,9,99,-1,8";
-            let v = str_to_vec(input);
-            let io = IO::from(5).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 1),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day5_2b() {
-        async fn t() {
-            let input = "3,9,7,9,10,9,4,9,99,-1,8";
-            let v = str_to_vec(input);
-            let io = IO::from(8).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 0),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
-    #[test]
-    fn test_run_day5b() {
-        async fn t() {
-            let input = "3,225,1,225,6,6,1100,1,238,225,104,0,2,171,209,224,1001,224,-1040,224,4,224,102,8,223,223,1001,224,4,224,1,223,224,223,102,65,102,224,101,-3575,224,224,4,224,102,8,223,223,101,2,224,224,1,223,224,223,1102,9,82,224,1001,224,-738,224,4,224,102,8,223,223,1001,224,2,224,1,223,224,223,1101,52,13,224,1001,224,-65,224,4,224,1002,223,8,223,1001,224,6,224,1,223,224,223,1102,82,55,225,1001,213,67,224,1001,224,-126,224,4,224,102,8,223,223,1001,224,7,224,1,223,224,223,1,217,202,224,1001,224,-68,224,4,224,1002,223,8,223,1001,224,1,224,1,224,223,223,1002,176,17,224,101,-595,224,224,4,224,102,8,223,223,101,2,224,224,1,224,223,223,1102,20,92,225,1102,80,35,225,101,21,205,224,1001,224,-84,224,4,224,1002,223,8,223,1001,224,1,224,1,224,223,223,1101,91,45,225,1102,63,5,225,1101,52,58,225,1102,59,63,225,1101,23,14,225,4,223,99,0,0,0,677,0,0,0,0,0,0,0,0,0,0,0,1105,0,99999,1105,227,247,1105,1,99999,1005,227,99999,1005,0,256,1105,1,99999,1106,227,99999,1106,0,265,1105,1,99999,1006,0,99999,1006,227,274,1105,1,99999,1105,1,280,1105,1,99999,1,225,225,225,1101,294,0,0,105,1,0,1105,1,99999,1106,0,300,1105,1,99999,1,225,225,225,1101,314,0,0,106,0,0,1105,1,99999,1008,677,677,224,1002,223,2,223,1006,224,329,101,1,223,223,1108,226,677,224,1002,223,2,223,1006,224,344,101,1,223,223,7,677,226,224,102,2,223,223,1006,224,359,1001,223,1,223,8,677,226,224,102,2,223,223,1005,224,374,1001,223,1,223,1107,677,226,224,102,2,223,223,1006,224,389,1001,223,1,223,1008,226,226,224,1002,223,2,223,1005,224,404,1001,223,1,223,7,226,677,224,102,2,223,223,1005,224,419,1001,223,1,223,1007,677,677,224,102,2,223,223,1006,224,434,1001,223,1,223,107,226,226,224,1002,223,2,223,1005,224,449,1001,223,1,223,1008,677,226,224,102,2,223,223,1006,224,464,1001,223,1,223,1007,677,226,224,1002,223,2,223,1005,224,479,1001,223,1,223,108,677,677,224,1002,223,2,223,1006,224,494,1001,223,1,223,108,226,226,224,1002,223,2,223,1006,224,509,101,1,223,223,8,226,677,224,102,2,223,223,1006,224,524,101,1,223,223,107,677,226,224,1002,223,2,223,1005,224,539,1001,223,1,223,8,226,226,224,102,2,223,223,1005,224,554,101,1,223,223,1108,677,226,224,102,2,223,223,1006,224,569,101,1,223,223,108,677,226,224,102,2,223,223,1006,224,584,1001,223,1,223,7,677,677,224,1002,223,2,223,1005,224,599,101,1,223,223,1007,226,226,224,102,2,223,223,1005,224,614,1001,223,1,223,1107,226,677,224,102,2,223,223,1006,224,629,101,1,223,223,1107,226,226,224,102,2,223,223,1005,224,644,1001,223,1,223,1108,677,677,224,1002,223,2,223,1005,224,659,101,1,223,223,107,677,677,224,1002,223,2,223,1006,224,674,1001,223,1,223,4,223,99,226";
-            let v = str_to_vec(input);
-            let io = IO::from(5).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 3629692),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
-
     #[test]
     fn test_run_day7b() {
         let input = "3,8,1001,8,10,8,105,1,0,0,21,38,55,80,97,118,199,280,361,442,99999,3,9,101,2,9,9,1002,9,5,9,1001,9,4,9,4,9,99,3,9,101,5,9,9,102,2,9,9,1001,9,5,9,4,9,99,3,9,1001,9,4,9,102,5,9,9,101,4,9,9,102,4,9,9,1001,9,4,9,4,9,99,3,9,1001,9,3,9,1002,9,2,9,101,3,9,9,4,9,99,3,9,101,5,9,9,1002,9,2,9,101,3,9,9,1002,9,5,9,4,9,99,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,2,9,9,4,9,3,9,1002,9,2,9,4,9,99,3,9,102,2,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,2,9,9,4,9,3,9,102,2,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,101,2,9,9,4,9,3,9,102,2,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,102,2,9,9,4,9,99,3,9,102,2,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,102,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,101,2,9,9,4,9,3,9,101,1,9,9,4,9,3,9,101,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,99,3,9,1002,9,2,9,4,9,3,9,101,2,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,102,2,9,9,4,9,3,9,102,2,9,9,4,9,3,9,1001,9,1,9,4,9,3,9,101,2,9,9,4,9,3,9,102,2,9,9,4,9,3,9,101,2,9,9,4,9,3,9,1001,9,1,9,4,9,99,3,9,102,2,9,9,4,9,3,9,101,1,9,9,4,9,3,9,1002,9,2,9,4,9,3,9,101,1,9,9,4,9,3,9,1001,9,2,9,4,9,3,9,1002,9,2,9,4,9,3,9,1002,9,2,9,4,9,3,9,1001,9,2,9,4,9,3,9,1001,9,1,9,4,9,3,9,102,2,9,9,4,9,99";
         let instrs = str_to_vec(input);
 
-        let phase_combinations = (5..10).permutations(5);
-        println!("all combinations {:?}", phase_combinations);
-        let mut max_val = 0;
-        let mut max_combination = Vec::new();
-        for combination in phase_combinations {
-            println!("checking combination {:?}", combination);
-            let val = get_thrust(&instrs, &combination).unwrap();
-            if val > max_val {
-                max_val = val;
-                max_combination = combination;
-            }
-        }
-
-        assert_eq!(max_val, 19581200);
-        assert_eq!(max_combination, vec![8, 9, 5, 6, 7]);
+        let result = phase::search(&instrs, &[5,6,7,8,9], Topology::Ring).unwrap();
+        assert_eq!(result.best().unwrap().signal, Some(19581200));
+        assert_eq!(result.best().unwrap().phases, vec![8, 9, 5, 6, 7]);
     }
 
     #[test]
-    fn test_run_day9a_1() {
-        async fn t() {
-            let input = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
-            let v = str_to_vec(input);
-            let v2 = v.clone();
-            let io = IO::new();
-            let mut comp = IntcodeComp::new(0, v, io);
-            comp.run().await;
-            assert_eq!(comp.io.history, v2);
-        }
-        block_on(t());
+    fn test_robot_example() {
+        // replays the outputs from the puzzle example, ignoring its input
+        let prog = str_to_vec("3,100,104,1,104,0,3,100,104,0,104,0,3,100,104,1,104,0,3,100,104,1,104,0,\
+3,100,104,0,104,1,3,100,104,1,104,0,3,100,104,1,104,0,99");
+        let mut robot = Robot::new(&prog, Colour::Black);
+        robot.run().unwrap();
+        assert_eq!(robot.painted(), 6);
+        assert_eq!(robot.pos, (0,-1));
+        assert_eq!(robot.dir, (-1,0));
+        assert_eq!(robot.render(), "..#\n..#\n##.\n");
     }
 
     #[test]
-    fn test_run_day9a_2() {
-        async fn t() {
-            let input = "1102,34915192,34915192,7,4,7,99,0";
-            let v = str_to_vec(input);
-            let io = IO::new();
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out.to_string().len(), 16),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
+    fn test_start_panel_not_painted() {
+        // looks at the white start panel, then halts without painting
+        let mut robot = Robot::new(&str_to_vec("3,100,99"), Colour::White);
+        robot.run().unwrap();
+        assert_eq!(robot.painted(), 0);
+        assert_eq!(robot.colour((0,0)), Colour::White);
     }
 
     #[test]
-    fn test_run_day9a_3() {
-        async fn t() {
-            let input = "104,1125899906842624,99";
-            let v = str_to_vec(input);
-            let io = IO::new();
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(out) => assert_eq!(out, 1125899906842624),
-                None => panic!("no output"),
-            }
-        }
-        block_on(t());
-    }
+    fn test_hull() {
+        let v = str_to_vec(BRAIN);
+        let mut robot = Robot::new(&v, Colour::Black);
+        robot.run().unwrap();
+        assert_eq!(robot.painted(), 2211);
 
-    #[test]
-    fn test_run_day9a() {
-        async fn t() {
-            let input = "1102,34463338,34463338,63,1007,63,34463338,63,1005,63,53,1101,0,3,1000,109,988,209,12,9,1000,209,6,209,3,203,0,1008,1000,1,63,1005,63,65,1008,1000,2,63,1005,63,904,1008,1000,0,63,1005,63,58,4,25,104,0,99,4,0,104,0,99,4,17,104,0,99,0,0,1101,0,396,1029,1101,0,356,1023,1101,401,0,1028,1101,24,0,1008,1101,33,0,1019,1101,35,0,1010,1102,359,1,1022,1102,32,1,1001,1101,37,0,1004,1101,0,31,1009,1101,0,30,1003,1101,28,0,1002,1102,1,36,1014,1102,20,1,1012,1101,21,0,1000,1101,0,22,1015,1102,23,1,1013,1102,1,1,1021,1102,1,39,1007,1102,26,1,1017,1101,0,38,1016,1101,0,437,1024,1102,432,1,1025,1101,0,421,1026,1101,0,29,1005,1101,27,0,1011,1102,1,0,1020,1101,0,25,1018,1101,0,414,1027,1102,34,1,1006,109,6,2108,33,-3,63,1005,63,201,1001,64,1,64,1105,1,203,4,187,1002,64,2,64,109,14,21108,40,40,-6,1005,1014,221,4,209,1105,1,225,1001,64,1,64,1002,64,2,64,109,-21,2102,1,3,63,1008,63,28,63,1005,63,251,4,231,1001,64,1,64,1106,0,251,1002,64,2,64,109,12,2101,0,-3,63,1008,63,21,63,1005,63,275,1001,64,1,64,1105,1,277,4,257,1002,64,2,64,109,-10,1207,1,27,63,1005,63,293,1105,1,299,4,283,1001,64,1,64,1002,64,2,64,109,9,21108,41,42,3,1005,1013,315,1105,1,321,4,305,1001,64,1,64,1002,64,2,64,109,-12,1202,6,1,63,1008,63,37,63,1005,63,347,4,327,1001,64,1,64,1105,1,347,1002,64,2,64,109,29,2105,1,-4,1105,1,365,4,353,1001,64,1,64,1002,64,2,64,109,-17,2108,32,-9,63,1005,63,387,4,371,1001,64,1,64,1105,1,387,1002,64,2,64,109,17,2106,0,1,4,393,1105,1,405,1001,64,1,64,1002,64,2,64,109,1,2106,0,-1,1001,64,1,64,1106,0,423,4,411,1002,64,2,64,109,-13,2105,1,9,4,429,1106,0,441,1001,64,1,64,1002,64,2,64,109,3,21107,42,41,-1,1005,1017,461,1001,64,1,64,1106,0,463,4,447,1002,64,2,64,109,-4,21107,43,44,1,1005,1015,481,4,469,1106,0,485,1001,64,1,64,1002,64,2,64,109,-6,21101,44,0,6,1008,1014,47,63,1005,63,505,1106,0,511,4,491,1001,64,1,64,1002,64,2,64,109,-6,1208,-1,32,63,1005,63,529,4,517,1105,1,533,1001,64,1,64,1002,64,2,64,109,11,1205,7,545,1106,0,551,4,539,1001,64,1,64,1002,64,2,64,109,11,21102,45,1,-7,1008,1017,48,63,1005,63,575,1001,64,1,64,1106,0,577,4,557,1002,64,2,64,109,-8,1206,5,593,1001,64,1,64,1105,1,595,4,583,1002,64,2,64,109,7,1206,-3,609,4,601,1106,0,613,1001,64,1,64,1002,64,2,64,109,-10,2101,0,-6,63,1008,63,39,63,1005,63,635,4,619,1106,0,639,1001,64,1,64,1002,64,2,64,109,-9,1208,0,39,63,1005,63,655,1106,0,661,4,645,1001,64,1,64,1002,64,2,64,109,4,2107,25,0,63,1005,63,681,1001,64,1,64,1105,1,683,4,667,1002,64,2,64,109,-5,2107,31,-2,63,1005,63,701,4,689,1106,0,705,1001,64,1,64,1002,64,2,64,109,19,1205,-1,719,4,711,1105,1,723,1001,64,1,64,1002,64,2,64,109,-17,1201,3,0,63,1008,63,24,63,1005,63,745,4,729,1106,0,749,1001,64,1,64,1002,64,2,64,109,13,21102,46,1,-3,1008,1015,46,63,1005,63,771,4,755,1105,1,775,1001,64,1,64,1002,64,2,64,109,-13,1207,4,32,63,1005,63,793,4,781,1106,0,797,1001,64,1,64,1002,64,2,64,109,7,2102,1,-9,63,1008,63,27,63,1005,63,821,1001,64,1,64,1105,1,823,4,803,1002,64,2,64,109,-18,1201,8,0,63,1008,63,25,63,1005,63,847,1001,64,1,64,1106,0,849,4,829,1002,64,2,64,109,23,21101,47,0,2,1008,1019,47,63,1005,63,871,4,855,1106,0,875,1001,64,1,64,1002,64,2,64,109,-22,1202,5,1,63,1008,63,19,63,1005,63,899,1001,64,1,64,1106,0,901,4,881,4,64,99,21102,27,1,1,21102,1,915,0,1105,1,922,21201,1,25165,1,204,1,99,109,3,1207,-2,3,63,1005,63,964,21201,-2,-1,1,21102,942,1,0,1105,1,922,22102,1,1,-1,21201,-2,-3,1,21101,0,957,0,1105,1,922,22201,1,-1,-2,1106,0,968,21201,-2,0,-2,109,-3,2105,1,0";
-            let v = str_to_vec(input);
-            let io = IO::from(1).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(output) => assert_eq!(output, 3497884671),
-                None => panic!("no output"),
-            };
-        }
-        block_on(t());
+        let mut robot = Robot::new(&v, Colour::White);
+        robot.run().unwrap();
+        assert!(robot.render().starts_with("####.####..##..#..#.#..#.####..##...##.\n"));
     }
-
-    #[test]
-    fn test_run_day9b() {
-        async fn t() {
-            let input = "1102,34463338,34463338,63,1007,63,34463338,63,1005,63,53,1101,0,3,1000,109,988,209,12,9,1000,209,6,209,3,203,0,1008,1000,1,63,1005,63,65,1008,1000,2,63,1005,63,904,1008,1000,0,63,1005,63,58,4,25,104,0,99,4,0,104,0,99,4,17,104,0,99,0,0,1101,0,396,1029,1101,0,356,1023,1101,401,0,1028,1101,24,0,1008,1101,33,0,1019,1101,35,0,1010,1102,359,1,1022,1102,32,1,1001,1101,37,0,1004,1101,0,31,1009,1101,0,30,1003,1101,28,0,1002,1102,1,36,1014,1102,20,1,1012,1101,21,0,1000,1101,0,22,1015,1102,23,1,1013,1102,1,1,1021,1102,1,39,1007,1102,26,1,1017,1101,0,38,1016,1101,0,437,1024,1102,432,1,1025,1101,0,421,1026,1101,0,29,1005,1101,27,0,1011,1102,1,0,1020,1101,0,25,1018,1101,0,414,1027,1102,34,1,1006,109,6,2108,33,-3,63,1005,63,201,1001,64,1,64,1105,1,203,4,187,1002,64,2,64,109,14,21108,40,40,-6,1005,1014,221,4,209,1105,1,225,1001,64,1,64,1002,64,2,64,109,-21,2102,1,3,63,1008,63,28,63,1005,63,251,4,231,1001,64,1,64,1106,0,251,1002,64,2,64,109,12,2101,0,-3,63,1008,63,21,63,1005,63,275,1001,64,1,64,1105,1,277,4,257,1002,64,2,64,109,-10,1207,1,27,63,1005,63,293,1105,1,299,4,283,1001,64,1,64,1002,64,2,64,109,9,21108,41,42,3,1005,1013,315,1105,1,321,4,305,1001,64,1,64,1002,64,2,64,109,-12,1202,6,1,63,1008,63,37,63,1005,63,347,4,327,1001,64,1,64,1105,1,347,1002,64,2,64,109,29,2105,1,-4,1105,1,365,4,353,1001,64,1,64,1002,64,2,64,109,-17,2108,32,-9,63,1005,63,387,4,371,1001,64,1,64,1105,1,387,1002,64,2,64,109,17,2106,0,1,4,393,1105,1,405,1001,64,1,64,1002,64,2,64,109,1,2106,0,-1,1001,64,1,64,1106,0,423,4,411,1002,64,2,64,109,-13,2105,1,9,4,429,1106,0,441,1001,64,1,64,1002,64,2,64,109,3,21107,42,41,-1,1005,1017,461,1001,64,1,64,1106,0,463,4,447,1002,64,2,64,109,-4,21107,43,44,1,1005,1015,481,4,469,1106,0,485,1001,64,1,64,1002,64,2,64,109,-6,21101,44,0,6,1008,1014,47,63,1005,63,505,1106,0,511,4,491,1001,64,1,64,1002,64,2,64,109,-6,1208,-1,32,63,1005,63,529,4,517,1105,1,533,1001,64,1,64,1002,64,2,64,109,11,1205,7,545,1106,0,551,4,539,1001,64,1,64,1002,64,2,64,109,11,21102,45,1,-7,1008,1017,48,63,1005,63,575,1001,64,1,64,1106,0,577,4,557,1002,64,2,64,109,-8,1206,5,593,1001,64,1,64,1105,1,595,4,583,1002,64,2,64,109,7,1206,-3,609,4,601,1106,0,613,1001,64,1,64,1002,64,2,64,109,-10,2101,0,-6,63,1008,63,39,63,1005,63,635,4,619,1106,0,639,1001,64,1,64,1002,64,2,64,109,-9,1208,0,39,63,1005,63,655,1106,0,661,4,645,1001,64,1,64,1002,64,2,64,109,4,2107,25,0,63,1005,63,681,1001,64,1,64,1105,1,683,4,667,1002,64,2,64,109,-5,2107,31,-2,63,1005,63,701,4,689,1106,0,705,1001,64,1,64,1002,64,2,64,109,19,1205,-1,719,4,711,1105,1,723,1001,64,1,64,1002,64,2,64,109,-17,1201,3,0,63,1008,63,24,63,1005,63,745,4,729,1106,0,749,1001,64,1,64,1002,64,2,64,109,13,21102,46,1,-3,1008,1015,46,63,1005,63,771,4,755,1105,1,775,1001,64,1,64,1002,64,2,64,109,-13,1207,4,32,63,1005,63,793,4,781,1106,0,797,1001,64,1,64,1002,64,2,64,109,7,2102,1,-9,63,1008,63,27,63,1005,63,821,1001,64,1,64,1105,1,823,4,803,1002,64,2,64,109,-18,1201,8,0,63,1008,63,25,63,1005,63,847,1001,64,1,64,1106,0,849,4,829,1002,64,2,64,109,23,21101,47,0,2,1008,1019,47,63,1005,63,871,4,855,1106,0,875,1001,64,1,64,1002,64,2,64,109,-22,1202,5,1,63,1008,63,19,63,1005,63,899,1001,64,1,64,1106,0,901,4,881,4,64,99,21102,27,1,1,21102,1,915,0,1105,1,922,21201,1,25165,1,204,1,99,109,3,1207,-2,3,63,1005,63,964,21201,-2,-1,1,21102,942,1,0,1105,1,922,22102,1,1,-1,21201,-2,-3,1,21101,0,957,0,1105,1,922,22201,1,-1,-2,1106,0,968,21201,-2,0,-2,109,-3,2105,1,0";
-            let v = str_to_vec(input);
-            let io = IO::from(2).await;
-            match IntcodeComp::new(0, v, io).run().await {
-                Some(output) => assert_eq!(output, 46470),
-                None => panic!("no output"),
-            };
-        }
-        block_on(t());
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,281 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs::read_to_string;
+
+use intcode::{str_to_vec, Event, IntcodeComp, IntcodeError, Numeric};
+
+type Pos = (Numeric, Numeric);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Dir {
+    North = 1,
+    South = 2,
+    West = 3,
+    East = 4,
+}
+
+const DIRS: [Dir; 4] = [Dir::North, Dir::South, Dir::West, Dir::East];
+
+impl Dir {
+    fn step(self, (x, y): Pos) -> Pos {
+        match self {
+            Dir::North => (x, y - 1),
+            Dir::South => (x, y + 1),
+            Dir::West => (x - 1, y),
+            Dir::East => (x + 1, y),
+        }
+    }
+
+    fn reverse(self) -> Dir {
+        match self {
+            Dir::North => Dir::South,
+            Dir::South => Dir::North,
+            Dir::West => Dir::East,
+            Dir::East => Dir::West,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Tile {
+    Wall = 0,
+    Open = 1,
+    Oxygen = 2,
+}
+
+impl Tile {
+    fn from_i64(status: Numeric) -> Option<Tile> {
+        match status {
+            0 => Some(Tile::Wall),
+            1 => Some(Tile::Open),
+            2 => Some(Tile::Oxygen),
+            _ => None,
+        }
+    }
+}
+
+struct Droid {
+    comp: IntcodeComp,
+    pos: Pos, // relative to where the droid started
+    map: HashMap<Pos, Tile>,
+}
+
+impl Droid {
+    fn new(instrs: &[Numeric]) -> Droid {
+        let mut map = HashMap::new();
+        map.insert((0, 0), Tile::Open);
+        Droid{comp: IntcodeComp::new(0, instrs.to_vec()), pos: (0, 0), map}
+    }
+
+    /// Try to move one square; the droid stays put if it hits a wall.
+    fn go(&mut self, dir: Dir) -> Result<Tile, IntcodeError> {
+        self.comp.push_input(dir as Numeric);
+        let tile = match self.comp.run_until_event()? {
+            Event::Output(status) => Tile::from_i64(status)
+                .ok_or_else(|| IntcodeError::BadOutput(self.comp.location(), status))?,
+            Event::NeedsInput => return Err(IntcodeError::NoInput(self.comp.location())),
+            Event::Halted => return Err(IntcodeError::ChannelClosed(self.comp.location())),
+        };
+        let next = dir.step(self.pos);
+        self.map.insert(next, tile);
+        if tile != Tile::Wall {
+            self.pos = next;
+        }
+        Ok(tile)
+    }
+
+    /// Visit every reachable square, depth first, backing up the way the
+    /// droid came so it ends where it started.
+    fn explore(&mut self) -> Result<(), IntcodeError> {
+        for &dir in DIRS.iter() {
+            if self.map.contains_key(&dir.step(self.pos)) {
+                continue;
+            }
+            if self.go(dir)? != Tile::Wall {
+                self.explore()?;
+                self.go(dir.reverse())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn oxygen(&self) -> Option<Pos> {
+        self.map.iter().find(|(_, t)| **t == Tile::Oxygen).map(|(p, _)| *p)
+    }
+
+    /// Moves from `from` to every open square of the map.
+    fn distances(&self, from: Pos) -> HashMap<Pos, usize> {
+        let mut dist = HashMap::new();
+        dist.insert(from, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+        while let Some(pos) = queue.pop_front() {
+            let d = dist[&pos];
+            for dir in DIRS.iter() {
+                let next = dir.step(pos);
+                let open = matches!(self.map.get(&next), Some(Tile::Open) | Some(Tile::Oxygen));
+                if open && !dist.contains_key(&next) {
+                    dist.insert(next, d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        dist
+    }
+
+    /// Fewest moves from the start to the oxygen system.
+    fn shortest_path(&self) -> Option<usize> {
+        self.distances((0, 0)).get(&self.oxygen()?).copied()
+    }
+
+    /// Minutes for oxygen to spread from the system to every open square.
+    fn fill_time(&self) -> Option<usize> {
+        self.distances(self.oxygen()?).values().max().copied()
+    }
+
+    fn render(&self) -> String {
+        let min_x = self.map.keys().map(|p| p.0).min().unwrap();
+        let max_x = self.map.keys().map(|p| p.0).max().unwrap();
+        let min_y = self.map.keys().map(|p| p.1).min().unwrap();
+        let max_y = self.map.keys().map(|p| p.1).max().unwrap();
+        let mut screen = String::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                screen.push(match self.map.get(&(x, y)) {
+                    _ if (x, y) == self.pos => 'D',
+                    Some(Tile::Wall) => '#',
+                    Some(Tile::Open) => '.',
+                    Some(Tile::Oxygen) => 'O',
+                    None => ' ',
+                });
+            }
+            screen.push('\n');
+        }
+        screen
+    }
+}
+
+fn main() {
+    let filename = env::args().nth(1).unwrap_or_else(|| "input.txt".to_string());
+    let text = read_to_string(&filename)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", filename, e));
+    let mut droid = Droid::new(&str_to_vec(text.trim()));
+    if let Err(e) = droid.explore() {
+        println!("{}", e);
+    }
+    print!("{}", droid.render());
+    println!("Moves to oxygen system: {:?}", droid.shortest_path());
+    println!("Minutes to fill with oxygen: {:?}", droid.fill_time());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::asm::assemble;
+
+    /// A program that plays the droid's remote control for `maze`, where
+    /// `D` is the start, `O` the oxygen system and anything but `.` a wall.
+    /// `rb` points at the droid's square; a move adds its offset to `rb` and
+    /// outputs the square, taking the move back if it was a wall.
+    fn remote(maze: &str) -> Vec<Numeric> {
+        let rows: Vec<&str> = maze.lines().filter(|l| !l.is_empty()).collect();
+        let width = rows.iter().map(|r| r.len()).max().unwrap();
+        let mut cells = Vec::new();
+        let mut start = 0;
+        for row in rows.iter() {
+            for x in 0..width {
+                match row.as_bytes().get(x) {
+                    Some(b'D') => {
+                        start = cells.len();
+                        cells.push("1");
+                    },
+                    Some(b'.') => cells.push("1"),
+                    Some(b'O') => cells.push("2"),
+                    _ => cells.push("0"),
+                }
+            }
+        }
+        let source = format!("
+                arb #grid+{start}
+        loop:   in -> [cmd]
+                eq [cmd], #1 -> [t]
+                jf [t], #south
+                add #0, #-{w} -> [d]
+                jt #1, #move
+        south:  eq [cmd], #2 -> [t]
+                jf [t], #west
+                add #0, #{w} -> [d]
+                jt #1, #move
+        west:   eq [cmd], #3 -> [t]
+                jf [t], #east
+                add #0, #-1 -> [d]
+                jt #1, #move
+        east:   add #0, #1 -> [d]
+        move:   arb [d]
+                out [rb+0]
+                jt [rb+0], #loop
+                mul [d], #-1 -> [d]
+                arb [d]
+                jt #1, #loop
+        cmd:    data 0
+        t:      data 0
+        d:      data 0
+        grid:   data {cells}
+        ", start = start, w = width, cells = cells.join(", "));
+        assemble(&source).unwrap()
+    }
+
+    const MAZE: &str = "
+#########
+#D..#...#
+###.#.#.#
+#...#.#O#
+#.###.#.#
+#.....#.#
+#########
+";
+
+    #[test]
+    fn test_explore() {
+        let mut droid = Droid::new(&remote(MAZE));
+        droid.explore().unwrap();
+        assert_eq!(droid.pos, (0, 0));
+        assert_eq!(droid.render(), &"
+ ### ### 
+#D..#...#
+ ##.#.#.#
+#...#.#O#
+#.###.#.#
+#.....#.#
+ ##### # 
+"[1..]);
+        assert_eq!(droid.oxygen(), Some((6, 2)));
+        assert_eq!(droid.shortest_path(), Some(20));
+        assert_eq!(droid.fill_time(), Some(20));
+    }
+
+    #[test]
+    fn test_fill_time() {
+        let maze = "
+ ##
+#D.##
+#.#..#
+#.O.#
+ ###
+";
+        let mut droid = Droid::new(&remote(maze));
+        droid.explore().unwrap();
+        assert_eq!(droid.oxygen(), Some((1, 2)));
+        assert_eq!(droid.shortest_path(), Some(3));
+        assert_eq!(droid.fill_time(), Some(4));
+    }
+
+    #[test]
+    fn test_droid_errors() {
+        let mut droid = Droid::new(&str_to_vec("3,10,99"));
+        assert!(matches!(droid.go(Dir::North), Err(IntcodeError::ChannelClosed(at)) if at.ptr == 2));
+        let mut droid = Droid::new(&str_to_vec("3,10,104,7,99"));
+        assert!(matches!(droid.go(Dir::North), Err(IntcodeError::BadOutput(_, 7))));
+        assert_eq!(droid.pos, (0, 0));
+    }
+}
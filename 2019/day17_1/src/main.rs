@@ -0,0 +1,337 @@
+use std::env;
+use std::fmt;
+use std::fs::read_to_string;
+
+use intcode::ascii::{text, values};
+use intcode::{str_to_vec, AsciiComp, IntcodeError, Numeric};
+
+type Pos = (Numeric, Numeric);
+
+/// What the camera sees, one row of characters per line.
+struct Image {
+    rows: Vec<Vec<u8>>,
+}
+
+impl Image {
+    fn parse(picture: &str) -> Image {
+        Image{rows: picture.lines().filter(|l| !l.is_empty()).map(|l| l.bytes().collect()).collect()}
+    }
+
+    fn get(&self, (x, y): Pos) -> u8 {
+        if x < 0 || y < 0 {
+            return b'.';
+        }
+        self.rows.get(y as usize).and_then(|r| r.get(x as usize)).copied().unwrap_or(b'.')
+    }
+
+    /// Scaffold, with or without the robot on it.
+    fn scaffold(&self, pos: Pos) -> bool {
+        matches!(self.get(pos), b'#' | b'^' | b'v' | b'<' | b'>')
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.rows.iter().enumerate()
+            .flat_map(|(y, r)| (0..r.len()).map(move |x| (x as Numeric, y as Numeric)))
+    }
+
+    /// Scaffold squares with scaffold on all four sides.
+    fn intersections(&self) -> Vec<Pos> {
+        self.positions().filter(|&(x, y)| {
+            self.scaffold((x, y)) && [(0, -1), (0, 1), (-1, 0), (1, 0)].iter()
+                .all(|(dx, dy)| self.scaffold((x + dx, y + dy)))
+        }).collect()
+    }
+
+    fn alignment(&self) -> Numeric {
+        self.intersections().iter().map(|(x, y)| x * y).sum()
+    }
+
+    /// Where the robot is and which way it faces, as a unit step.
+    fn robot(&self) -> Option<(Pos, Pos)> {
+        self.positions().find_map(|p| match self.get(p) {
+            b'^' => Some((p, (0, -1))),
+            b'v' => Some((p, (0, 1))),
+            b'<' => Some((p, (-1, 0))),
+            b'>' => Some((p, (1, 0))),
+            _ => None,
+        })
+    }
+
+    /// The moves that take the robot along the scaffold to its far end,
+    /// going straight over every intersection.
+    fn path(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let (mut pos, mut dir) = match self.robot() {
+            Some(r) => r,
+            None => return moves,
+        };
+        loop {
+            let (dx, dy) = dir;
+            let (turn, next) = if self.scaffold((pos.0 + dy, pos.1 - dx)) {
+                (Turn::Left, (dy, -dx))
+            } else if self.scaffold((pos.0 - dy, pos.1 + dx)) {
+                (Turn::Right, (-dy, dx))
+            } else {
+                return moves;
+            };
+            dir = next;
+            let mut steps = 0;
+            while self.scaffold((pos.0 + dir.0, pos.1 + dir.1)) {
+                pos = (pos.0 + dir.0, pos.1 + dir.1);
+                steps += 1;
+            }
+            moves.push(Move(turn, steps));
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Turn {
+    Left,
+    Right,
+}
+
+/// Turn, then go forward this many squares.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+struct Move(Turn, usize);
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Turn::Left => write!(f, "L,{}", self.1),
+            Turn::Right => write!(f, "R,{}", self.1),
+        }
+    }
+}
+
+/// Tokens as the robot reads them: comma separated.
+fn join<T: fmt::Display>(tokens: &[T]) -> String {
+    tokens.iter().map(|t| t.to_string()).collect::<Vec<String>>().join(",")
+}
+
+/// A sequence split into functions and a main routine calling them.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Compressed<T> {
+    main: Vec<usize>, // indexes into `functions`
+    functions: Vec<Vec<T>>,
+}
+
+impl<T: fmt::Display + Clone> Compressed<T> {
+    /// Main as function names, `A,B,A,...`.
+    fn main_routine(&self) -> String {
+        join(&self.main.iter().map(|f| (b'A' + *f as u8) as char).collect::<Vec<char>>())
+    }
+
+    fn expand(&self) -> Vec<T> {
+        self.main.iter().flat_map(|f| self.functions[*f].iter().cloned()).collect()
+    }
+}
+
+/// Split `tokens` into a main routine calling at most `functions`
+/// functions, where main and every function are at most `limit` characters
+/// written out. Functions are tried longest first, so the first answer
+/// found uses few calls.
+fn compress<T: fmt::Display + Clone + PartialEq>(tokens: &[T], functions: usize, limit: usize) -> Option<Compressed<T>> {
+    fn search<T: fmt::Display + Clone + PartialEq>(tokens: &[T], functions: usize, limit: usize,
+                                                   found: &mut Compressed<T>) -> bool {
+        if tokens.is_empty() {
+            return true;
+        }
+        // calls are one letter each, plus commas
+        if 2 * found.main.len() + 1 > limit {
+            return false;
+        }
+        for f in 0..found.functions.len() {
+            let body = found.functions[f].clone();
+            if tokens.starts_with(&body) {
+                found.main.push(f);
+                if search(&tokens[body.len()..], functions, limit, found) {
+                    return true;
+                }
+                found.main.pop();
+            }
+        }
+        if found.functions.len() < functions {
+            let longest = (1..=tokens.len()).take_while(|n| join(&tokens[..*n]).len() <= limit).last().unwrap_or(0);
+            for n in (1..=longest).rev() {
+                found.main.push(found.functions.len());
+                found.functions.push(tokens[..n].to_vec());
+                if search(&tokens[n..], functions, limit, found) {
+                    return true;
+                }
+                found.functions.pop();
+                found.main.pop();
+            }
+        }
+        false
+    }
+
+    let mut found = Compressed{main: Vec::new(), functions: Vec::new()};
+    match search(tokens, functions, limit, &mut found) {
+        true => Some(found),
+        false => None,
+    }
+}
+
+/// Take a picture with the cameras.
+fn camera(program: &[Numeric]) -> Result<Image, IntcodeError> {
+    let mut comp = AsciiComp::from_program(0, program.to_vec());
+    let out = comp.run()?;
+    Ok(Image::parse(&text(&out)))
+}
+
+/// Wake the robot, give it its routines, and return the dust it reports.
+fn vacuum(program: &[Numeric], routines: &Compressed<Move>) -> Result<Option<Numeric>, IntcodeError> {
+    let mut comp = AsciiComp::from_program(0, program.to_vec());
+    comp.comp.set_mem(0, 2).unwrap();
+    comp.send_line(&routines.main_routine());
+    for f in 0..3 {
+        comp.send_line(&routines.functions.get(f).map_or(String::new(), |body| join(body)));
+    }
+    comp.send_line("n"); // no continuous video feed
+    let out = comp.run()?;
+    Ok(values(&out).last().copied())
+}
+
+fn main() {
+    let filename = env::args().nth(1).unwrap_or_else(|| "input.txt".to_string());
+    let text = read_to_string(&filename)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", filename, e));
+    let program = str_to_vec(text.trim());
+    let image = match camera(&program) {
+        Ok(image) => image,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        },
+    };
+    println!("Sum of alignment parameters: {}", image.alignment());
+
+    let path = image.path();
+    println!("Path: {}", join(&path));
+    match compress(&path, 3, 20) {
+        Some(routines) => {
+            assert_eq!(routines.expand(), path);
+            println!("Main: {}", routines.main_routine());
+            for (f, body) in routines.functions.iter().enumerate() {
+                println!("{}: {}", (b'A' + f as u8) as char, join(body));
+            }
+            match vacuum(&program, &routines) {
+                Ok(dust) => println!("Dust collected: {:?}", dust),
+                Err(e) => println!("{}", e),
+            }
+        },
+        None => println!("Path does not fit in three functions"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::asm::assemble;
+
+    const CROSSES: &str = "
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..
+";
+
+    const LOOPS: &str = "
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+";
+
+    #[test]
+    fn test_intersections() {
+        let image = Image::parse(CROSSES);
+        assert_eq!(image.intersections(), vec![(2, 2), (2, 4), (6, 4), (10, 4)]);
+        assert_eq!(image.alignment(), 76);
+    }
+
+    #[test]
+    fn test_path() {
+        let image = Image::parse(LOOPS);
+        assert_eq!(image.robot(), Some(((0, 6), (0, -1))));
+        assert_eq!(join(&image.path()), "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2");
+    }
+
+    #[test]
+    fn test_compress() {
+        let path = Image::parse(LOOPS).path();
+        let routines = compress(&path, 3, 20).unwrap();
+        assert_eq!(routines.expand(), path);
+        assert!(routines.main_routine().len() <= 20);
+        assert!(routines.functions.len() <= 3);
+        for body in routines.functions.iter() {
+            assert!(join(body).len() <= 20);
+        }
+
+        // the search works on any tokens
+        let letters: Vec<char> = "abcabcxyxyabcxy".chars().collect();
+        let routines = compress(&letters, 2, 11).unwrap();
+        assert_eq!(routines.main_routine(), "A,A,B,B,A,B");
+        assert_eq!(routines.functions, vec![vec!['a', 'b', 'c'], vec!['x', 'y']]);
+        assert_eq!(compress(&letters, 1, 11), None);
+    }
+
+    /// A stand-in for the ASCII program: it shows `picture`, or once woken
+    /// by a 2 at address 0 reads five lines and reports how many characters
+    /// it was sent, plus 10000 so it is not read as a character.
+    fn ascii_program(picture: &str) -> Vec<Numeric> {
+        let codes: Vec<String> = picture.trim_start().bytes().map(|b| b.to_string()).collect();
+        let source = format!("
+                add [0], [0] -> [t]
+                eq [t], #4 -> [t]
+                jt [t], #wake
+                arb #picture
+        show:   jf [rb+0], #done
+                out [rb+0]
+                arb #1
+                jt #1, #show
+        wake:   in -> [c]
+                add [n], #1 -> [n]
+                eq [c], #10 -> [t]
+                add [lines], [t] -> [lines]
+                eq [lines], #5 -> [t]
+                jf [t], #wake
+                add [n], #10000 -> [n]
+                out [n]
+        done:   halt
+        t:      data 0
+        c:      data 0
+        n:      data 0
+        lines:  data 0
+        picture: data {}, 0
+        ", codes.join(", "));
+        assemble(&source).unwrap()
+    }
+
+    #[test]
+    fn test_camera_and_vacuum() {
+        let program = ascii_program(LOOPS);
+        let image = camera(&program).unwrap();
+        assert_eq!(image.rows.len(), 15);
+        let routines = compress(&image.path(), 3, 20).unwrap();
+        let sent = routines.main_routine().len() + 1
+            + routines.functions.iter().map(|f| join(f).len() + 1).sum::<usize>()
+            + (3 - routines.functions.len()) + 2;
+        assert_eq!(vacuum(&program, &routines), Ok(Some(10000 + sent as Numeric)));
+    }
+}
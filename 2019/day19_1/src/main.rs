@@ -0,0 +1,215 @@
+use std::env;
+use std::fs::read_to_string;
+
+use intcode::{str_to_vec, Event, IntcodeComp, IntcodeError, Numeric, Snapshot};
+
+/// The squares of one row that the beam pulls, first to last.
+type Span = (Numeric, Numeric);
+
+/// Probes the tractor beam, one run of the drone program per square.
+struct Beam {
+    comp: IntcodeComp,
+    start: Snapshot, // the program before it has run
+    runs: usize, // drone programs run so far
+}
+
+impl Beam {
+    fn new(instrs: &[Numeric]) -> Beam {
+        let comp = IntcodeComp::new(0, instrs.to_vec());
+        let start = comp.snapshot();
+        Beam{comp, start, runs: 0}
+    }
+
+    /// Whether the beam pulls on square `(x, y)`.
+    fn pulled(&mut self, x: Numeric, y: Numeric) -> Result<bool, IntcodeError> {
+        self.runs += 1;
+        self.comp.restore(&self.start);
+        self.comp.push_input(x);
+        self.comp.push_input(y);
+        match self.comp.run_until_event()? {
+            Event::Output(v) => Ok(v == 1),
+            Event::NeedsInput => Err(IntcodeError::NoInput(self.comp.location())),
+            // halting without an answer closes the drone's end of the line
+            Event::Halted => Err(IntcodeError::ChannelClosed(self.comp.location())),
+        }
+    }
+
+    /// The beam's span on row `y`, looking no further right than `limit`.
+    /// The beam widens away from the emitter, so neither edge can be left
+    /// of where it was on an earlier row; `prev` is that row's span.
+    fn span(&mut self, y: Numeric, prev: Span, limit: Numeric) -> Result<Option<Span>, IntcodeError> {
+        let mut left = prev.0;
+        while left < limit && !self.pulled(left, y)? {
+            left += 1;
+        }
+        if left >= limit {
+            return Ok(None);
+        }
+        let mut right = prev.1.max(left);
+        while right + 1 < limit && self.pulled(right + 1, y)? {
+            right += 1;
+        }
+        Ok(Some((left, right)))
+    }
+
+    /// The spans of the first `size` rows within `size` columns. Rows
+    /// near the emitter can miss the beam altogether.
+    fn scan(&mut self, size: Numeric) -> Result<Vec<Option<Span>>, IntcodeError> {
+        let mut prev = (0, 0);
+        let mut rows = Vec::new();
+        for y in 0..size {
+            let span = self.span(y, prev, size)?;
+            if let Some(s) = span {
+                prev = s;
+            }
+            rows.push(span);
+        }
+        Ok(rows)
+    }
+
+    /// The top-left corner of the `size` by `size` square closest to the
+    /// emitter that fits in the beam, looking no further down than
+    /// `max_y`. Each row's left edge is tried as the bottom-left corner,
+    /// and the square fits if its top-right corner is in the beam too.
+    fn fit(&mut self, size: Numeric, max_y: Numeric) -> Result<Option<(Numeric, Numeric)>, IntcodeError> {
+        let mut prev = (0, 0);
+        for y in 0..max_y {
+            // a row that misses the beam is searched no further than ten
+            // times its distance, far wider than any beam
+            let span = match self.span(y, prev, prev.1.max(10 * (y + 1)))? {
+                Some(s) => s,
+                None => continue,
+            };
+            prev = span;
+            let top = y - (size - 1);
+            if top >= 0 && span.1 - span.0 + 1 >= size && self.pulled(span.0 + size - 1, top)? {
+                return Ok(Some((span.0, top)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn affected(rows: &[Option<Span>]) -> Numeric {
+    rows.iter().flatten().map(|(l, r)| r - l + 1).sum()
+}
+
+fn render(rows: &[Option<Span>], width: Numeric) -> String {
+    let mut screen = String::new();
+    for row in rows {
+        for x in 0..width {
+            screen.push(match row {
+                Some((l, r)) if *l <= x && x <= *r => '#',
+                _ => '.',
+            });
+        }
+        screen.push('\n');
+    }
+    screen
+}
+
+fn main() {
+    let filename = env::args().nth(1).unwrap_or_else(|| "input.txt".to_string());
+    let text = read_to_string(&filename)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", filename, e));
+    let mut beam = Beam::new(&str_to_vec(text.trim()));
+    match beam.scan(50) {
+        Ok(rows) => {
+            print!("{}", render(&rows, 50));
+            println!("Points affected: {} ({} runs)", affected(&rows), beam.runs);
+        },
+        Err(e) => {
+            println!("{}", e);
+            return;
+        },
+    }
+
+    beam.runs = 0;
+    match beam.fit(100, 10_000) {
+        Ok(Some((x, y))) => println!("Ship fits at {},{}: {} ({} runs)", x, y, x * 10000 + y, beam.runs),
+        Ok(None) => println!("Ship does not fit"),
+        Err(e) => println!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::asm::assemble;
+
+    /// A drone program for a beam that pulls where `lo * y <= 10 * x <= hi * y`.
+    fn drone(lo: Numeric, hi: Numeric) -> Vec<Numeric> {
+        let source = format!("
+                in -> [x]
+                in -> [y]
+                mul [x], #10 -> [a]
+                mul [y], #{lo} -> [b]
+                lt [a], [b] -> [t]
+                jt [t], #miss
+                mul [y], #{hi} -> [b]
+                lt [b], [a] -> [t]
+                jt [t], #miss
+                out #1
+                halt
+        miss:   out #0
+                halt
+        x:      data 0
+        y:      data 0
+        a:      data 0
+        b:      data 0
+        t:      data 0
+        ", lo = lo, hi = hi);
+        assemble(&source).unwrap()
+    }
+
+    fn in_beam(lo: Numeric, hi: Numeric, x: Numeric, y: Numeric) -> bool {
+        lo * y <= 10 * x && 10 * x <= hi * y
+    }
+
+    #[test]
+    fn test_scan() {
+        let mut beam = Beam::new(&drone(7, 12));
+        let rows = beam.scan(50).unwrap();
+        let brute = (0..50).flat_map(|y| (0..50).map(move |x| (x, y)))
+            .filter(|(x, y)| in_beam(7, 12, *x, *y))
+            .count();
+        assert_eq!(affected(&rows), brute as Numeric);
+        assert!(beam.runs < 200, "{} runs", beam.runs);
+        assert_eq!(&render(&rows[..4], 4), "#...\n.#..\n..#.\n...#\n");
+    }
+
+    #[test]
+    fn test_gaps() {
+        // a thin beam that misses whole rows near the emitter
+        let mut beam = Beam::new(&drone(31, 33));
+        let rows = beam.scan(20).unwrap();
+        assert_eq!(rows[1], None);
+        assert_eq!(rows[4], Some((13, 13)));
+        for y in 0..20 {
+            for x in 0..20 {
+                let lit = matches!(rows[y as usize], Some((l, r)) if l <= x && x <= r);
+                assert_eq!(lit, in_beam(31, 33, x, y), "{},{}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit() {
+        let mut beam = Beam::new(&drone(7, 12));
+        let corner = beam.fit(10, 1000).unwrap();
+        let square = |x: Numeric, y: Numeric| (0..10).all(|d| in_beam(7, 12, x + d, y) && in_beam(7, 12, x, y + d)
+                                                          && in_beam(7, 12, x + d, y + 9) && in_beam(7, 12, x + 9, y + d));
+        let brute = (0..1000).flat_map(|y| (0..1000).map(move |x| (x, y))).find(|(x, y)| square(*x, *y));
+        assert_eq!(corner, brute);
+        assert!(beam.runs < 1000, "{} runs", beam.runs);
+    }
+
+    #[test]
+    fn test_no_answer() {
+        let mut beam = Beam::new(&[3,0,3,0,99]);
+        let err = beam.pulled(0, 0).unwrap_err();
+        assert!(matches!(err, IntcodeError::ChannelClosed(at) if at.ptr == 4), "{}", err);
+        let mut beam = Beam::new(&[99]);
+        assert!(beam.scan(3).is_err());
+    }
+}
@@ -0,0 +1,485 @@
+use std::env;
+use std::fmt;
+use std::fs::read_to_string;
+use std::str::FromStr;
+
+use intcode::ascii::{text, values};
+use intcode::{str_to_vec, AsciiComp, IntcodeError, Numeric};
+
+/// Registers A-I read the hull 1-9 squares ahead; T and J are writable.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Reg {
+    Sensor(usize), // squares ahead, 1 for A
+    T,
+    J,
+}
+
+impl Reg {
+    fn parse(s: &str) -> Option<Reg> {
+        match s.as_bytes() {
+            [b'T'] => Some(Reg::T),
+            [b'J'] => Some(Reg::J),
+            [c @ b'A'..=b'I'] => Some(Reg::Sensor((c - b'A' + 1) as usize)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Reg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reg::Sensor(n) => write!(f, "{}", (b'A' + *n as u8 - 1) as char),
+            Reg::T => write!(f, "T"),
+            Reg::J => write!(f, "J"),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Gate {
+    And,
+    Or,
+    Not,
+}
+
+const GATES: [Gate; 3] = [Gate::And, Gate::Or, Gate::Not];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+struct Instr {
+    gate: Gate,
+    src: Reg,
+    dst: Reg, // T or J
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let gate = match self.gate {
+            Gate::And => "AND",
+            Gate::Or => "OR",
+            Gate::Not => "NOT",
+        };
+        write!(f, "{} {} {}", gate, self.src, self.dst)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Mode {
+    Walk, // sensors A-D
+    Run, // sensors A-I
+}
+
+impl Mode {
+    fn sensors(self) -> usize {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+
+    fn command(self) -> &'static str {
+        match self {
+            Mode::Walk => "WALK",
+            Mode::Run => "RUN",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct ScriptError {
+    line: usize, // 1-based source line
+    msg: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+const MAX_INSTRS: usize = 15; // the droid's memory
+
+/// A springscript program, and the mode its file asked for, if any.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+struct Script {
+    instrs: Vec<Instr>,
+    mode: Option<Mode>,
+}
+
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    /// One instruction per line, `WALK` or `RUN` last; blank lines and
+    /// `#` comments are ignored.
+    fn from_str(source: &str) -> Result<Script, ScriptError> {
+        let mut script = Script::default();
+        for (n, line) in source.lines().enumerate() {
+            let err = |msg: String| Err(ScriptError{line: n + 1, msg});
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if script.mode.is_some() {
+                return err(format!("'{}' after the mode", line));
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let gate = match words[0] {
+                "WALK" | "RUN" if words.len() == 1 => {
+                    script.mode = Some(if words[0] == "WALK" { Mode::Walk } else { Mode::Run });
+                    continue;
+                },
+                "AND" => Gate::And,
+                "OR" => Gate::Or,
+                "NOT" => Gate::Not,
+                w => return err(format!("unknown instruction '{}'", w)),
+            };
+            if words.len() != 3 {
+                return err(format!("'{}' takes two registers", words[0]));
+            }
+            let src = match Reg::parse(words[1]) {
+                Some(r) => r,
+                None => return err(format!("bad register '{}'", words[1])),
+            };
+            let dst = match Reg::parse(words[2]) {
+                Some(r @ Reg::T) | Some(r @ Reg::J) => r,
+                _ => return err(format!("cannot write to '{}'", words[2])),
+            };
+            script.instrs.push(Instr{gate, src, dst});
+        }
+        if script.instrs.len() > MAX_INSTRS {
+            return Err(ScriptError{line: 0, msg: format!("{} instructions, at most {} fit", script.instrs.len(), MAX_INSTRS)});
+        }
+        Ok(script)
+    }
+}
+
+impl Script {
+    /// Sensors past what `mode` offers, e.g. `E` in WALK mode.
+    fn check(&self, mode: Mode) -> Result<(), String> {
+        for i in self.instrs.iter() {
+            if let Reg::Sensor(n) = i.src {
+                if n > mode.sensors() {
+                    return Err(format!("'{}' needs RUN mode", i));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the droid jumps, given the hull ahead of it (`true` is
+    /// ground; past the end counts as ground).
+    fn jumps(&self, ahead: &[bool]) -> bool {
+        let (mut t, mut j) = (false, false);
+        for i in self.instrs.iter() {
+            let x = match i.src {
+                Reg::Sensor(n) => ahead.get(n - 1).copied().unwrap_or(true),
+                Reg::T => t,
+                Reg::J => j,
+            };
+            let y = if i.dst == Reg::T { &mut t } else { &mut j };
+            *y = match i.gate {
+                Gate::And => x && *y,
+                Gate::Or => x || *y,
+                Gate::Not => !x,
+            };
+        }
+        j
+    }
+
+    /// Walk the droid over `hull`, starting on its first square. Jumps
+    /// land four squares on.
+    fn crosses(&self, hull: &[bool]) -> bool {
+        let mut pos = 0;
+        while pos < hull.len() {
+            if !hull[pos] {
+                return false;
+            }
+            pos += if self.jumps(&hull[pos + 1..]) { 4 } else { 1 };
+        }
+        true
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in self.instrs.iter() {
+            writeln!(f, "{}", i)?;
+        }
+        if let Some(mode) = self.mode {
+            writeln!(f, "{}", mode.command())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Outcome {
+    Damage(Numeric), // made it across; the hull damage reported
+    Fell(String), // the droid's last moments, as drawn
+}
+
+impl Outcome {
+    /// The hull from the droid's square onwards, from the first frame of
+    /// a fall: the row under the `@`.
+    fn hull(&self) -> Option<Vec<bool>> {
+        let picture = match self {
+            Outcome::Fell(p) => p,
+            Outcome::Damage(_) => return None,
+        };
+        let lines: Vec<&str> = picture.lines().collect();
+        let row = lines.iter().position(|l| l.contains('@'))?;
+        let col = lines[row].find('@')?;
+        let ground = lines.get(row + 1)?;
+        Some(ground.bytes().skip(col).map(|b| b == b'#').collect())
+    }
+}
+
+/// Load `script` into a fresh droid and send it out.
+fn run(program: &[Numeric], script: &Script, mode: Mode) -> Result<Outcome, IntcodeError> {
+    let mut comp = AsciiComp::from_program(0, program.to_vec());
+    comp.run()?; // the prompt
+    for i in script.instrs.iter() {
+        comp.send_line(&i.to_string());
+    }
+    let out = comp.command(mode.command())?;
+    Ok(match values(&out).last() {
+        Some(damage) => Outcome::Damage(*damage),
+        None => Outcome::Fell(text(&out)),
+    })
+}
+
+/// Every instruction that can be written in `mode`.
+fn instructions(mode: Mode) -> Vec<Instr> {
+    let mut srcs: Vec<Reg> = (1..=mode.sensors()).map(Reg::Sensor).collect();
+    srcs.push(Reg::T);
+    srcs.push(Reg::J);
+    let mut all = Vec::new();
+    for &gate in GATES.iter() {
+        for &src in srcs.iter() {
+            for &dst in [Reg::T, Reg::J].iter() {
+                all.push(Instr{gate, src, dst});
+            }
+        }
+    }
+    all
+}
+
+/// What a search found, and what it cost.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Found {
+    script: Option<(Script, Numeric)>, // the first script to get across, and its damage
+    runs: usize, // scripts sent to the droid
+    hulls: Vec<Vec<bool>>, // hulls the droid fell on
+}
+
+/// Try every script of up to `max_len` instructions, shortest first. A
+/// script only goes to the droid (`send`) if it crosses every hull an
+/// earlier script fell on, so most are ruled out without running anything.
+fn search<F>(mode: Mode, max_len: usize, mut send: F) -> Result<Found, IntcodeError>
+    where F: FnMut(&Script) -> Result<Outcome, IntcodeError> {
+    let all = instructions(mode);
+    let mut found = Found{script: None, runs: 0, hulls: Vec::new()};
+    for len in 1..=max_len.min(MAX_INSTRS) {
+        // an odometer over `all`, one digit per instruction
+        let mut digits = vec![0; len];
+        loop {
+            let instrs: Vec<Instr> = digits.iter().map(|d| all[*d]).collect();
+            let script = Script{instrs, mode: Some(mode)};
+            // a script whose last instruction leaves J alone never jumps
+            if script.instrs[len - 1].dst == Reg::J && found.hulls.iter().all(|h| script.crosses(h)) {
+                found.runs += 1;
+                let outcome = send(&script)?;
+                match outcome.hull() {
+                    Some(hull) => found.hulls.push(hull),
+                    None => {
+                        if let Outcome::Damage(damage) = outcome {
+                            found.script = Some((script, damage));
+                        }
+                        return Ok(found);
+                    },
+                }
+            }
+            match digits.iter().rposition(|d| d + 1 < all.len()) {
+                Some(i) => {
+                    digits[i] += 1;
+                    for d in digits[i + 1..].iter_mut() {
+                        *d = 0;
+                    }
+                },
+                None => break,
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn usage() -> ! {
+    eprintln!("usage: day21_1 <program> <script> [walk|run]");
+    eprintln!("       day21_1 <program> search [walk|run] [max instructions]");
+    std::process::exit(1);
+}
+
+fn parse_mode(arg: Option<&String>) -> Option<Mode> {
+    match arg.map(|s| s.as_str()) {
+        Some("walk") => Some(Mode::Walk),
+        Some("run") => Some(Mode::Run),
+        Some(_) => usage(),
+        None => None,
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        usage();
+    }
+    let text = read_to_string(&args[1])
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", args[1], e));
+    let program = str_to_vec(text.trim());
+
+    if args[2] == "search" {
+        let mode = parse_mode(args.get(3)).unwrap_or(Mode::Walk);
+        let max_len = args.get(4).map_or(4, |n| n.parse().unwrap_or_else(|_| usage()));
+        match search(mode, max_len, |s| run(&program, s, mode)) {
+            Ok(Found{script: Some((script, damage)), runs, ..}) => {
+                print!("{}", script);
+                println!("Hull damage: {} ({} scripts run)", damage, runs);
+            },
+            Ok(found) => println!("No script of {} instructions made it ({} scripts run)", max_len, found.runs),
+            Err(e) => println!("{}", e),
+        }
+        return;
+    }
+
+    let source = read_to_string(&args[2])
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", args[2], e));
+    let script: Script = match source.parse() {
+        Ok(s) => s,
+        Err(e) => {
+            println!("{}: {}", args[2], e);
+            return;
+        },
+    };
+    let mode = parse_mode(args.get(3)).or(script.mode).unwrap_or(Mode::Walk);
+    if let Err(e) = script.check(mode) {
+        println!("{}: {}", args[2], e);
+        return;
+    }
+    match run(&program, &script, mode) {
+        Ok(Outcome::Damage(damage)) => println!("Hull damage: {}", damage),
+        Ok(Outcome::Fell(picture)) => println!("{}", picture),
+        Err(e) => println!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::asm::assemble;
+
+    fn hull(s: &str) -> Vec<bool> {
+        s.bytes().map(|b| b == b'#').collect()
+    }
+
+    const JUMP_HOLES: &str = "
+# jump if there is a hole ahead and ground to land on
+NOT A J
+NOT B T
+OR T J
+NOT C T
+OR T J
+AND D J
+WALK
+";
+
+    #[test]
+    fn test_parse() {
+        let script: Script = JUMP_HOLES.parse().unwrap();
+        assert_eq!(script.instrs.len(), 6);
+        assert_eq!(script.mode, Some(Mode::Walk));
+        assert_eq!(script.instrs[5], Instr{gate: Gate::And, src: Reg::Sensor(4), dst: Reg::J});
+        assert_eq!(script.to_string(), JUMP_HOLES.trim_start().lines().skip(1).map(|l| format!("{}\n", l)).collect::<String>());
+
+        assert_eq!("NOT A B".parse::<Script>(), Err(ScriptError{line: 1, msg: "cannot write to 'B'".to_string()}));
+        assert_eq!("\nJUMP A J".parse::<Script>().unwrap_err().line, 2);
+        assert_eq!("RUN\nNOT A J".parse::<Script>().unwrap_err().msg, "'NOT A J' after the mode");
+        assert!("NOT A J\n".repeat(16).parse::<Script>().is_err());
+
+        let run: Script = "NOT E J".parse().unwrap();
+        assert_eq!(run.check(Mode::Walk), Err("'NOT E J' needs RUN mode".to_string()));
+        assert_eq!(run.check(Mode::Run), Ok(()));
+    }
+
+    #[test]
+    fn test_crosses() {
+        let script: Script = JUMP_HOLES.parse().unwrap();
+        assert!(script.crosses(&hull("#####.###########")));
+        assert!(script.crosses(&hull("#####..#.########")));
+        // needs to look past D to get this one
+        assert!(!script.crosses(&hull("#####.#.##..#.###")));
+    }
+
+    /// A stand-in droid: after the prompt it reads lines up to the mode,
+    /// then reports damage for RUN or shows a fall for WALK.
+    fn droid() -> Vec<Numeric> {
+        let fall = "\nDidn't make it across:\n\n.....\n@....\n##.##\n\n";
+        let codes: Vec<String> = fall.bytes().map(|b| b.to_string()).collect();
+        let source = format!("
+                out #62
+                out #10
+        line:   in -> [first]
+                eq [first], #82 -> [run]
+                eq [first], #87 -> [t]
+                add [run], [t] -> [t]
+        rest:   in -> [c]
+                eq [c], #10 -> [e]
+                jf [e], #rest
+                jf [t], #line
+                jt [run], #damage
+                arb #fall
+        show:   jf [rb+0], #done
+                out [rb+0]
+                arb #1
+                jt #1, #show
+        damage: out #19358
+        done:   halt
+        first:  data 0
+        run:    data 0
+        c:      data 0
+        e:      data 0
+        t:      data 0
+        fall:   data {}, 0
+        ", codes.join(", "));
+        assemble(&source).unwrap()
+    }
+
+    #[test]
+    fn test_run() {
+        let script: Script = JUMP_HOLES.parse().unwrap();
+        assert_eq!(run(&droid(), &script, Mode::Run), Ok(Outcome::Damage(19358)));
+        let fell = run(&droid(), &script, Mode::Walk).unwrap();
+        assert_eq!(fell.hull(), Some(hull("##.##")));
+        match fell {
+            Outcome::Fell(picture) => assert!(picture.contains("Didn't make it across:")),
+            _ => panic!("should have fallen"),
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        // a droid simulated on hulls it has been told about
+        let hulls = [hull("#####.###########"), hull("#####..#.########"), hull("#####...#########")];
+        let mut sent = 0;
+        let found = search(Mode::Walk, 4, |script| {
+            sent += 1;
+            Ok(match hulls.iter().find(|h| !script.crosses(h)) {
+                Some(h) => Outcome::Fell(format!("@\n{}", h.iter().map(|g| if *g { '#' } else { '.' }).collect::<String>())),
+                None => Outcome::Damage(1),
+            })
+        }).unwrap();
+        let (script, _) = found.script.unwrap();
+        assert!(hulls.iter().all(|h| script.crosses(h)));
+        assert_eq!(found.runs, sent);
+        assert!(found.runs <= 4, "{} runs", found.runs);
+        assert!(!found.hulls.is_empty());
+    }
+}
@@ -0,0 +1,603 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs::read_to_string;
+use std::io::{self, BufRead, Write};
+
+use intcode::{str_to_vec, AsciiComp, AsciiOutput, IntcodeError, Numeric, Snapshot};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum End {
+    Waiting, // asking for the next command
+    Halted,
+    Stuck, // still running when the step budget ran out
+}
+
+/// What the game printed after a command.
+#[derive(PartialEq, Eq, Debug, Clone)]
+struct Reply {
+    text: String,
+    end: End,
+}
+
+/// Something that plays the game and can be saved and restored.
+trait Console {
+    type Save: Clone;
+
+    /// Send a command (nothing for the opening text) and read the reply.
+    fn send(&mut self, command: &str) -> Result<Reply, IntcodeError>;
+    fn save(&self) -> Self::Save;
+    fn load(&mut self, save: &Self::Save);
+}
+
+/// The game running on the VM, saved as snapshots.
+struct Droid {
+    ascii: AsciiComp,
+    budget: u64, // instructions a reply may take before the droid counts as stuck
+}
+
+impl Droid {
+    fn new(instrs: &[Numeric]) -> Droid {
+        Droid{ascii: AsciiComp::from_program(0, instrs.to_vec()), budget: 10_000_000}
+    }
+}
+
+impl Console for Droid {
+    type Save = Snapshot;
+
+    fn send(&mut self, command: &str) -> Result<Reply, IntcodeError> {
+        if !command.is_empty() {
+            self.ascii.send_line(command);
+        }
+        let (out, stopped) = self.ascii.run_for(self.budget)?;
+        let text = out.iter().map(|o| match o {
+            AsciiOutput::Line(l) => format!("{}\n", l),
+            AsciiOutput::Value(v) => format!("{}\n", v),
+        }).collect();
+        let end = match (stopped, self.ascii.halted) {
+            (false, _) => End::Stuck,
+            (true, true) => End::Halted,
+            (true, false) => End::Waiting,
+        };
+        Ok(Reply{text, end})
+    }
+
+    fn save(&self) -> Snapshot {
+        self.ascii.comp.snapshot()
+    }
+
+    fn load(&mut self, save: &Snapshot) {
+        self.ascii.restore(save);
+    }
+}
+
+/// A room as the game describes it.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+/// Every room described in `text`, in order. Being thrown out of a room
+/// describes two.
+fn rooms(text: &str) -> Vec<Room> {
+    let mut rooms: Vec<Room> = Vec::new();
+    let mut list = None;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("== ") && line.ends_with(" ==") {
+            rooms.push(Room{name: line[3..line.len() - 3].to_string(), ..Room::default()});
+            list = None;
+        } else if line == "Doors here lead:" {
+            list = Some(true);
+        } else if line == "Items here:" {
+            list = Some(false);
+        } else if let (Some(item), Some(room)) = (line.strip_prefix("- "), rooms.last_mut()) {
+            match list {
+                Some(true) => room.doors.push(item.to_string()),
+                Some(false) => room.items.push(item.to_string()),
+                None => (),
+            }
+        } else if line.is_empty() {
+            list = None;
+        }
+    }
+    rooms
+}
+
+fn opposite(dir: &str) -> &'static str {
+    match dir {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        d => panic!("not a direction: {}", d),
+    }
+}
+
+/// The longest run of digits in `text`, such as the airlock password.
+fn password(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_ascii_digit()).max_by_key(|s| s.len()).filter(|s| !s.is_empty()).map(|s| s.to_string())
+}
+
+/// Most items the solver will try sets of; each set is a bit in a `u32`.
+const MAX_HELD: usize = 31;
+
+/// The game, with a map of what has been seen so far.
+struct Adventure<C: Console> {
+    console: C,
+    room: String, // where the droid is
+    doors: HashMap<String, Vec<(String, Option<String>)>>, // room to doors and where they lead, if known
+    items: HashMap<String, Vec<String>>, // items left in each room
+    inventory: Vec<String>,
+    checkpoint: Option<(String, String)>, // the room and door that weigh the droid
+    halted: bool,
+}
+
+impl<C: Console> Adventure<C> {
+    fn new(console: C) -> Result<(Adventure<C>, Reply), IntcodeError> {
+        let mut adventure = Adventure{
+            console,
+            room: String::new(),
+            doors: HashMap::new(),
+            items: HashMap::new(),
+            inventory: Vec::new(),
+            checkpoint: None,
+            halted: false,
+        };
+        let reply = adventure.command("")?;
+        Ok((adventure, reply))
+    }
+
+    /// Send a command and update the map from the reply.
+    fn command(&mut self, command: &str) -> Result<Reply, IntcodeError> {
+        let reply = self.console.send(command)?;
+        self.halted = reply.end != End::Waiting;
+        let seen = rooms(&reply.text);
+        for room in seen.iter() {
+            let doors = self.doors.entry(room.name.clone()).or_default();
+            for d in room.doors.iter() {
+                if !doors.iter().any(|(door, _)| door == d) {
+                    doors.push((d.clone(), None));
+                }
+            }
+            self.items.insert(room.name.clone(), room.items.clone());
+        }
+        let from = self.room.clone();
+        if let (Some(first), Some(last)) = (seen.first(), seen.last()) {
+            if !from.is_empty() {
+                if let Some(door) = self.doors.get_mut(&from).and_then(|ds| ds.iter_mut().find(|(d, _)| d == command)) {
+                    door.1 = Some(first.name.clone());
+                }
+                if seen.len() > 1 && last.name == from {
+                    self.checkpoint = Some((from, command.to_string()));
+                }
+            }
+            self.room = last.name.clone();
+        }
+        if let Some(item) = reply.text.lines().find_map(|l| l.strip_prefix("You take the ")) {
+            let item = item.trim_end_matches('.').to_string();
+            if let Some(here) = self.items.get_mut(&self.room) {
+                here.retain(|i| *i != item);
+            }
+            self.inventory.push(item);
+        }
+        if let Some(item) = reply.text.lines().find_map(|l| l.strip_prefix("You drop the ")) {
+            let item = item.trim_end_matches('.').to_string();
+            self.inventory.retain(|i| *i != item);
+            self.items.entry(self.room.clone()).or_default().push(item);
+        }
+        Ok(reply)
+    }
+
+    /// Take `item` if it does no harm: the game keeps going and the droid
+    /// can still move. Otherwise put everything back as it was.
+    fn take_safely(&mut self, item: &str) -> Result<bool, IntcodeError> {
+        let save = self.console.save();
+        let (room, items, inventory) = (self.room.clone(), self.items.clone(), self.inventory.clone());
+        let mut safe = self.command(&format!("take {}", item))?.end == End::Waiting
+            && self.inventory.iter().any(|i| i == item);
+        // with no door to try, there is no telling whether it stops the droid
+        let door = self.doors.get(&self.room).and_then(|ds| ds.first()).map(|(d, _)| d.clone());
+        match door {
+            Some(door) if safe => {
+                let reply = self.command(&door)?;
+                safe = reply.end == End::Waiting && !rooms(&reply.text).is_empty();
+                if safe && self.room != room {
+                    self.command(opposite(&door))?;
+                }
+            },
+            _ => safe = false,
+        }
+        if !safe {
+            self.console.load(&save);
+            self.room = room;
+            self.items = items;
+            self.inventory = inventory;
+            self.halted = false;
+        }
+        Ok(safe)
+    }
+
+    /// Visit every room, taking every safe item, and come back.
+    fn explore(&mut self) -> Result<(), IntcodeError> {
+        let mut visited = HashSet::new();
+        self.explore_from(&mut visited)
+    }
+
+    fn explore_from(&mut self, visited: &mut HashSet<String>) -> Result<(), IntcodeError> {
+        let here = self.room.clone();
+        visited.insert(here.clone());
+        for item in self.items.get(&here).cloned().unwrap_or_default() {
+            self.take_safely(&item)?;
+        }
+        let doors: Vec<String> = self.doors[&here].iter().map(|(d, _)| d.clone()).collect();
+        for door in doors {
+            if self.checkpoint.as_ref() == Some(&(here.clone(), door.clone())) {
+                continue;
+            }
+            self.command(&door)?;
+            if self.room == here {
+                continue; // thrown back
+            }
+            if !visited.contains(&self.room) {
+                self.explore_from(visited)?;
+            }
+            self.command(opposite(&door))?;
+        }
+        Ok(())
+    }
+
+    /// Doors to go through to get from where the droid is to `to`.
+    fn route(&self, to: &str) -> Option<Vec<String>> {
+        let mut came: HashMap<String, (String, String)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.room.clone());
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                let mut path = Vec::new();
+                let mut at = room;
+                while let Some((prev, door)) = came.get(&at) {
+                    path.push(door.clone());
+                    at = prev.clone();
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (door, next) in self.doors.get(&room).into_iter().flatten() {
+                if let Some(next) = next {
+                    if *next != self.room && !came.contains_key(next) {
+                        came.insert(next.clone(), (room.clone(), door.clone()));
+                        queue.push_back(next.clone());
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Explore, then walk onto the pressure-sensitive floor with each set
+    /// of items until one weighs right. Every try starts from a snapshot
+    /// taken at the checkpoint holding everything. A set that is too light
+    /// rules out its subsets, and one too heavy its supersets.
+    fn solve(&mut self) -> Result<Option<Reply>, IntcodeError> {
+        self.explore()?;
+        let (checkpoint, door) = match self.checkpoint.clone() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        for step in self.route(&checkpoint).unwrap_or_default() {
+            self.command(&step)?;
+        }
+        let save = self.console.save();
+        let (held, items) = (self.inventory.clone(), self.items.clone());
+        if held.len() > MAX_HELD {
+            return Ok(None);
+        }
+        let (mut too_light, mut too_heavy): (Vec<u32>, Vec<u32>) = (Vec::new(), Vec::new());
+        for mask in (0..1u32 << held.len()).rev() {
+            if too_light.iter().any(|l| mask & l == mask) || too_heavy.iter().any(|h| mask & h == *h) {
+                continue;
+            }
+            self.console.load(&save);
+            self.room = checkpoint.clone();
+            self.inventory = held.clone();
+            self.items = items.clone();
+            for (i, item) in held.iter().enumerate() {
+                if mask & (1 << i) == 0 {
+                    self.command(&format!("drop {}", item))?;
+                }
+            }
+            let reply = self.command(&door)?;
+            if reply.text.contains("heavier than the detected") {
+                too_light.push(mask);
+            } else if reply.text.contains("lighter than the detected") {
+                too_heavy.push(mask);
+            } else {
+                return Ok(Some(reply));
+            }
+        }
+        Ok(None)
+    }
+
+    fn render_map(&self) -> String {
+        let mut names: Vec<&String> = self.doors.keys().collect();
+        names.sort();
+        let mut out = String::new();
+        for name in names {
+            let here = if *name == self.room { " (here)" } else { "" };
+            out.push_str(&format!("{}{}\n", name, here));
+            for (door, to) in self.doors[name].iter() {
+                out.push_str(&format!("  {} -> {}\n", door, to.as_deref().unwrap_or("?")));
+            }
+            for item in self.items.get(name).into_iter().flatten() {
+                out.push_str(&format!("  item: {}\n", item));
+            }
+        }
+        out
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let filename = args.get(1).cloned().unwrap_or_else(|| "input.txt".to_string());
+    let text = read_to_string(&filename)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", filename, e));
+    let (mut game, opening) = match Adventure::new(Droid::new(&str_to_vec(text.trim()))) {
+        Ok(g) => g,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        },
+    };
+
+    if args.get(2).map(|s| s.as_str()) == Some("auto") {
+        match game.solve() {
+            Ok(Some(reply)) => {
+                print!("{}", reply.text);
+                println!("Password: {:?}", password(&reply.text));
+            },
+            Ok(None) => print!("No way past the checkpoint\n{}", game.render_map()),
+            Err(e) => println!("{}", e),
+        }
+        return;
+    }
+
+    // interactive: `map` shows what has been seen, `auto` finishes the game
+    print!("{}", opening.text);
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.unwrap();
+        let reply = match line.trim() {
+            "map" => {
+                print!("{}", game.render_map());
+                continue;
+            },
+            "auto" => match game.solve() {
+                Ok(Some(reply)) => reply,
+                Ok(None) => {
+                    println!("No way past the checkpoint");
+                    continue;
+                },
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                },
+            },
+            command => match game.command(command) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                },
+            },
+        };
+        print!("{}", reply.text);
+        io::stdout().flush().unwrap();
+        if game.halted {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use intcode::asm::assemble;
+
+    const HULL_BREACH: &str = "
+
+== Hull Breach ==
+You got in through a hole in the floor here.
+
+Doors here lead:
+- north
+- west
+
+Items here:
+- mouse
+- giant electromagnet
+
+Command?
+";
+
+    #[test]
+    fn test_rooms() {
+        let rooms = rooms(HULL_BREACH);
+        assert_eq!(rooms, vec![Room{
+            name: "Hull Breach".to_string(),
+            doors: vec!["north".to_string(), "west".to_string()],
+            items: vec!["mouse".to_string(), "giant electromagnet".to_string()],
+        }]);
+        assert_eq!(password("typing 2105377 on the keypad at the main airlock."), Some("2105377".to_string()));
+        assert_eq!(password("no digits"), None);
+    }
+
+    /// A small ship played in Rust, so the solver can be tested without a
+    /// game program.
+    #[derive(Clone)]
+    struct Ship {
+        rooms: HashMap<&'static str, Vec<(&'static str, &'static str)>>, // room to doors
+        items: HashMap<&'static str, Vec<&'static str>>,
+        weights: HashMap<&'static str, u32>,
+        target: u32, // weight the floor wants
+        at: &'static str,
+        inventory: Vec<&'static str>,
+        loads: usize, // saves loaded
+    }
+
+    impl Ship {
+        fn new() -> Ship {
+            let mut rooms = HashMap::new();
+            rooms.insert("Hull Breach", vec![("north", "Kitchen"), ("west", "Stables")]);
+            rooms.insert("Kitchen", vec![("south", "Hull Breach"), ("east", "Security Checkpoint")]);
+            rooms.insert("Stables", vec![("east", "Hull Breach")]);
+            rooms.insert("Security Checkpoint", vec![("west", "Kitchen"), ("north", "Pressure-Sensitive Floor")]);
+            let mut items = HashMap::new();
+            items.insert("Hull Breach", vec!["mouse", "giant electromagnet"]);
+            items.insert("Kitchen", vec!["spoon", "infinite loop"]);
+            items.insert("Stables", vec!["boots", "molten lava", "hay"]);
+            let weights = [("mouse", 1), ("spoon", 2), ("boots", 4), ("hay", 8)].iter().copied().collect();
+            Ship{rooms, items, weights, target: 9, at: "Hull Breach", inventory: Vec::new(), loads: 0}
+        }
+
+        fn describe(&self, room: &str) -> String {
+            let mut out = format!("\n\n\n== {} ==\nA room.\n\nDoors here lead:\n", room);
+            for (door, _) in self.rooms[room].iter() {
+                out.push_str(&format!("- {}\n", door));
+            }
+            let items = self.items.get(room).cloned().unwrap_or_default();
+            if !items.is_empty() {
+                out.push_str("\nItems here:\n");
+                for item in items {
+                    out.push_str(&format!("- {}\n", item));
+                }
+            }
+            out
+        }
+    }
+
+    impl Console for Ship {
+        type Save = Ship;
+
+        fn send(&mut self, command: &str) -> Result<Reply, IntcodeError> {
+            let waiting = |text: String| Ok(Reply{text: text + "\nCommand?\n", end: End::Waiting});
+            if command.is_empty() {
+                return waiting(self.describe(self.at));
+            }
+            if let Some(item) = command.strip_prefix("take ") {
+                let here = self.items.get_mut(self.at).unwrap();
+                let i = here.iter().position(|i| *i == item).unwrap();
+                let item = here.remove(i);
+                match item {
+                    "infinite loop" => return Ok(Reply{text: String::new(), end: End::Stuck}),
+                    "molten lava" => return Ok(Reply{text: "\nThe molten lava is way too hot! You melt!\n".to_string(), end: End::Halted}),
+                    _ => (),
+                }
+                self.inventory.push(item);
+                return waiting(format!("\nYou take the {}.\n", item));
+            }
+            if let Some(item) = command.strip_prefix("drop ") {
+                let i = self.inventory.iter().position(|i| *i == item).unwrap();
+                let item = self.inventory.remove(i);
+                self.items.entry(self.at).or_default().push(item);
+                return waiting(format!("\nYou drop the {}.\n", item));
+            }
+            if self.inventory.contains(&"giant electromagnet") {
+                return waiting("\nThe giant electromagnet is stuck to you.  You can't move!!\n".to_string());
+            }
+            let to = self.rooms[self.at].iter().find(|(d, _)| *d == command).unwrap().1;
+            if to == "Pressure-Sensitive Floor" {
+                let weight: u32 = self.inventory.iter().map(|i| self.weights[i]).sum();
+                let floor = "\n\n\n== Pressure-Sensitive Floor ==\nAnalyzing...\n\nDoors here lead:\n- south\n\n";
+                if weight == self.target {
+                    return Ok(Reply{text: format!("{}You should be able to get in by typing 4096 on the keypad.\n", floor),
+                                    end: End::Halted});
+                }
+                let alert = match weight < self.target {
+                    true => "A loud, robotic voice says \"Alert! Droids on this ship are heavier than the detected value!\"",
+                    false => "A loud, robotic voice says \"Alert! Droids on this ship are lighter than the detected value!\"",
+                };
+                return waiting(format!("{}{} and you are ejected back to the checkpoint.\n{}", floor, alert, self.describe(self.at)));
+            }
+            self.at = to;
+            waiting(self.describe(to))
+        }
+
+        fn save(&self) -> Ship {
+            self.clone()
+        }
+
+        fn load(&mut self, save: &Ship) {
+            let loads = self.loads + 1;
+            *self = save.clone();
+            self.loads = loads;
+        }
+    }
+
+    #[test]
+    fn test_explore() {
+        let (mut game, opening) = Adventure::new(Ship::new()).unwrap();
+        assert_eq!(rooms(&opening.text)[0].name, "Hull Breach");
+        game.explore().unwrap();
+        assert_eq!(game.room, "Hull Breach");
+        let mut held = game.inventory.clone();
+        held.sort();
+        assert_eq!(held, vec!["boots", "hay", "mouse", "spoon"]);
+        assert_eq!(game.checkpoint, Some(("Security Checkpoint".to_string(), "north".to_string())));
+        assert_eq!(game.items["Hull Breach"], vec!["giant electromagnet"]);
+        assert_eq!(game.route("Security Checkpoint"), Some(vec!["north".to_string(), "east".to_string()]));
+        assert!(game.render_map().contains("Kitchen\n  south -> Hull Breach\n  east -> Security Checkpoint\n"));
+        assert!(!game.halted);
+    }
+
+    #[test]
+    fn test_take_without_doors() {
+        let (mut game, _) = Adventure::new(Ship::new()).unwrap();
+        game.doors.get_mut("Hull Breach").unwrap().clear();
+        assert_eq!(game.take_safely("mouse"), Ok(false));
+        assert!(game.inventory.is_empty());
+        assert_eq!(game.items["Hull Breach"], vec!["mouse", "giant electromagnet"]);
+    }
+
+    #[test]
+    fn test_solve() {
+        let (mut game, _) = Adventure::new(Ship::new()).unwrap();
+        let reply = game.solve().unwrap().unwrap();
+        assert_eq!(password(&reply.text), Some("4096".to_string()));
+        let mut held = game.inventory.clone();
+        held.sort();
+        assert_eq!(held, vec!["hay", "mouse"]);
+        assert!(game.halted);
+        // 3 unsafe items, and fewer tries than the 16 sets
+        assert!(game.console.loads < 3 + 16, "{} loads", game.console.loads);
+    }
+
+    #[test]
+    fn test_droid() {
+        // describes a room, then loops forever on any command
+        let mut text = String::from("== Cell ==\n\nDoors here lead:\n- north\n\nCommand?\n");
+        text = text.bytes().map(|b| b.to_string()).collect::<Vec<String>>().join(", ");
+        let source = format!("
+                arb #text
+        show:   jf [rb+0], #read
+                out [rb+0]
+                arb #1
+                jt #1, #show
+        read:   in -> [c]
+        spin:   jt #1, #spin
+        c:      data 0
+        text:   data {}, 0
+        ", text);
+        let mut droid = Droid::new(&assemble(&source).unwrap());
+        droid.budget = 1000;
+        let (mut game, opening) = Adventure::new(droid).unwrap();
+        assert_eq!(opening.end, End::Waiting);
+        assert_eq!(game.room, "Cell");
+        let save = game.console.save();
+        assert_eq!(game.command("north").unwrap().end, End::Stuck);
+        assert!(game.halted);
+        game.console.load(&save);
+        assert_eq!(game.console.send("").unwrap().end, End::Waiting);
+    }
+}
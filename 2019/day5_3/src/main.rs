@@ -200,9 +200,10 @@ fn main() -> () {
     println!("output: {:?}", io.output);
 }
 
-
+#[cfg(test)]
 mod tests {
     use super::*;
+    use intcode::fuzz;
 
     #[test]
     fn test_str_to_vec() {
@@ -257,6 +258,39 @@ mod tests {
         assert_eq!(io.output[0], 1001);
     }
 
+    /// This interpreter, for `intcode::fuzz`. It has no relative mode, and
+    /// words are i32.
+    struct Day5;
+
+    impl fuzz::Implementation for Day5 {
+        fn name(&self) -> String {
+            "day5_3".to_string()
+        }
+
+        fn run(&self, program: &[i64], input: &[i64], _max_steps: u64) -> fuzz::Outcome {
+            let mut v: Vec<Numeric> = program.iter().map(|x| *x as Numeric).collect();
+            let mut io = IO{input: input.iter().map(|x| *x as Numeric).collect(), output: Vec::new()};
+            let end = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run(&mut v, &mut io))) {
+                Ok(_) => fuzz::End::Halted,
+                Err(e) => fuzz::End::Fault(match e.downcast_ref::<&str>() {
+                    Some(msg) => msg.to_string(),
+                    None => e.downcast_ref::<String>().cloned().unwrap_or_default(),
+                }),
+            };
+            let widen = |v: &Vec<Numeric>| v.iter().map(|x| *x as i64).collect();
+            fuzz::Outcome::new(widen(&io.output), widen(&v), end)
+        }
+    }
+
+    #[test]
+    fn test_fuzz() {
+        let config = fuzz::Config{relative: false, code_writes: false, loose_words: false, ..fuzz::Config::default()};
+        let impls: Vec<Box<dyn fuzz::Implementation>> = vec![Box::new(fuzz::Interpreter), Box::new(Day5)];
+        if let Err(d) = fuzz::fuzz(&config, &impls, 1, 300) {
+            panic!("{}", d);
+        }
+    }
+
     #[test]
     fn test_run_day5b() {
         let input = "3,225,1,225,6,6,1100,1,238,225,104,0,2,171,209,224,1001,224,-1040,224,4,224,102,8,223,223,1001,224,4,224,1,223,224,223,102,65,102,224,101,-3575,224,224,4,224,102,8,223,223,101,2,224,224,1,223,224,223,1102,9,82,224,1001,224,-738,224,4,224,102,8,223,223,1001,224,2,224,1,223,224,223,1101,52,13,224,1001,224,-65,224,4,224,1002,223,8,223,1001,224,6,224,1,223,224,223,1102,82,55,225,1001,213,67,224,1001,224,-126,224,4,224,102,8,223,223,1001,224,7,224,1,223,224,223,1,217,202,224,1001,224,-68,224,4,224,1002,223,8,223,1001,224,1,224,1,224,223,223,1002,176,17,224,101,-595,224,224,4,224,102,8,223,223,101,2,224,224,1,224,223,223,1102,20,92,225,1102,80,35,225,101,21,205,224,1001,224,-84,224,4,224,1002,223,8,223,1001,224,1,224,1,224,223,223,1101,91,45,225,1102,63,5,225,1101,52,58,225,1102,59,63,225,1101,23,14,225,4,223,99,0,0,0,677,0,0,0,0,0,0,0,0,0,0,0,1105,0,99999,1105,227,247,1105,1,99999,1005,227,99999,1005,0,256,1105,1,99999,1106,227,99999,1106,0,265,1105,1,99999,1006,0,99999,1006,227,274,1105,1,99999,1105,1,280,1105,1,99999,1,225,225,225,1101,294,0,0,105,1,0,1105,1,99999,1106,0,300,1105,1,99999,1,225,225,225,1101,314,0,0,106,0,0,1105,1,99999,1008,677,677,224,1002,223,2,223,1006,224,329,101,1,223,223,1108,226,677,224,1002,223,2,223,1006,224,344,101,1,223,223,7,677,226,224,102,2,223,223,1006,224,359,1001,223,1,223,8,677,226,224,102,2,223,223,1005,224,374,1001,223,1,223,1107,677,226,224,102,2,223,223,1006,224,389,1001,223,1,223,1008,226,226,224,1002,223,2,223,1005,224,404,1001,223,1,223,7,226,677,224,102,2,223,223,1005,224,419,1001,223,1,223,1007,677,677,224,102,2,223,223,1006,224,434,1001,223,1,223,107,226,226,224,1002,223,2,223,1005,224,449,1001,223,1,223,1008,677,226,224,102,2,223,223,1006,224,464,1001,223,1,223,1007,677,226,224,1002,223,2,223,1005,224,479,1001,223,1,223,108,677,677,224,1002,223,2,223,1006,224,494,1001,223,1,223,108,226,226,224,1002,223,2,223,1006,224,509,101,1,223,223,8,226,677,224,102,2,223,223,1006,224,524,101,1,223,223,107,677,226,224,1002,223,2,223,1005,224,539,1001,223,1,223,8,226,226,224,102,2,223,223,1005,224,554,101,1,223,223,1108,677,226,224,102,2,223,223,1006,224,569,101,1,223,223,108,677,226,224,102,2,223,223,1006,224,584,1001,223,1,223,7,677,677,224,1002,223,2,223,1005,224,599,101,1,223,223,1007,226,226,224,102,2,223,223,1005,224,614,1001,223,1,223,1107,226,677,224,102,2,223,223,1006,224,629,101,1,223,223,1107,226,226,224,102,2,223,223,1005,224,644,1001,223,1,223,1108,677,677,224,1002,223,2,223,1005,224,659,101,1,223,223,107,677,677,224,1002,223,2,223,1006,224,674,1001,223,1,223,4,223,99,226";
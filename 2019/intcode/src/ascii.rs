@@ -0,0 +1,204 @@
+//! ASCII text IO for programs that talk in lines rather than numbers.
+//!
+//! `AsciiComp` sends a `&str` as character codes followed by a newline and
+//! gathers the machine's output back into lines. Puzzles often finish by
+//! printing one large number after the text (dust collected, hull damage);
+//! any value outside the ASCII range is handed back on its own as
+//! `AsciiOutput::Value` instead of being mangled into a character.
+
+use crate::comp::{Event, IntcodeComp};
+use crate::error::IntcodeError;
+use crate::snapshot::Snapshot;
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum AsciiOutput {
+    Line(String), // a line of text, without its newline
+    Value(Numeric), // an output that is not an ASCII character
+}
+
+pub struct AsciiComp {
+    pub comp: IntcodeComp,
+    pub halted: bool,
+    partial: String, // text since the last newline
+}
+
+fn is_ascii(val: Numeric) -> bool {
+    (0..128).contains(&val)
+}
+
+impl AsciiComp {
+    pub fn new(comp: IntcodeComp) -> AsciiComp {
+        AsciiComp{comp, halted: false, partial: String::new()}
+    }
+
+    pub fn from_program(name: usize, program: Vec<Numeric>) -> AsciiComp {
+        AsciiComp::new(IntcodeComp::new(name, program))
+    }
+
+    /// Queue `line` and a newline as input.
+    pub fn send_line(&mut self, line: &str) {
+        for c in line.chars() {
+            self.comp.push_input(c as Numeric);
+        }
+        self.comp.push_input('\n' as Numeric);
+    }
+
+    fn flush(&mut self, out: &mut Vec<AsciiOutput>) {
+        if !self.partial.is_empty() {
+            out.push(AsciiOutput::Line(std::mem::take(&mut self.partial)));
+        }
+    }
+
+    fn output(&mut self, val: Numeric, out: &mut Vec<AsciiOutput>) {
+        match val {
+            10 => out.push(AsciiOutput::Line(std::mem::take(&mut self.partial))),
+            val if is_ascii(val) => self.partial.push(val as u8 as char),
+            val => {
+                self.flush(out);
+                out.push(AsciiOutput::Value(val));
+            },
+        }
+    }
+
+    /// Run until the machine wants input that has not been sent, or halts.
+    /// Text not ended by a newline, such as a prompt, is returned as a line
+    /// when the machine stops.
+    pub fn run(&mut self) -> Result<Vec<AsciiOutput>, IntcodeError> {
+        let mut out = Vec::new();
+        loop {
+            match self.comp.run_until_event()? {
+                Event::Output(val) => self.output(val, &mut out),
+                Event::NeedsInput => break,
+                Event::Halted => {
+                    self.halted = true;
+                    break;
+                },
+            }
+        }
+        self.flush(&mut out);
+        Ok(out)
+    }
+
+    /// Like `run`, but give up after `steps` instructions. Also returns
+    /// whether the machine stopped by itself; if not, it can be run again
+    /// from where it was, and an unfinished line is held back until then.
+    pub fn run_for(&mut self, steps: u64) -> Result<(Vec<AsciiOutput>, bool), IntcodeError> {
+        let mut out = Vec::new();
+        let mut stopped = false;
+        for _ in 0..steps {
+            match self.comp.step()? {
+                Some(Event::Output(val)) => self.output(val, &mut out),
+                Some(Event::NeedsInput) => stopped = true,
+                Some(Event::Halted) => {
+                    self.halted = true;
+                    stopped = true;
+                },
+                None => (),
+            }
+            if stopped {
+                self.flush(&mut out);
+                break;
+            }
+        }
+        Ok((out, stopped))
+    }
+
+    /// Put the machine back in the state of `snap`, dropping any unfinished
+    /// line. A machine saved after halting halts again when run.
+    pub fn restore(&mut self, snap: &Snapshot) {
+        self.comp.restore(snap);
+        self.partial.clear();
+        self.halted = false;
+    }
+
+    /// Send a line and run until the machine stops again.
+    pub fn command(&mut self, line: &str) -> Result<Vec<AsciiOutput>, IntcodeError> {
+        self.send_line(line);
+        self.run()
+    }
+}
+
+/// The text lines of some output, joined with newlines.
+pub fn text(out: &[AsciiOutput]) -> String {
+    let lines: Vec<&str> = out.iter().filter_map(|o| match o {
+        AsciiOutput::Line(l) => Some(l.as_str()),
+        AsciiOutput::Value(_) => None,
+    }).collect();
+    lines.join("\n")
+}
+
+/// The non-ASCII values of some output, in order.
+pub fn values(out: &[AsciiOutput]) -> Vec<Numeric> {
+    out.iter().filter_map(|o| match o {
+        AsciiOutput::Value(v) => Some(*v),
+        AsciiOutput::Line(_) => None,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    // greets, echoes one line back, then reports a score
+    const ECHO: &str = "
+            out #72
+            out #105
+            out #10
+            out #63
+    loop:   in -> [c]
+            out [c]
+            eq [c], #10 -> [t]
+            jf [t], #loop
+            out #12345
+            halt
+    c:      data 0
+    t:      data 0
+    ";
+
+    #[test]
+    fn test_echo() {
+        let mut comp = AsciiComp::from_program(0, assemble(ECHO).unwrap());
+        let out = comp.run().unwrap();
+        assert_eq!(out, vec![AsciiOutput::Line("Hi".to_string()), AsciiOutput::Line("?".to_string())]);
+        assert!(!comp.halted);
+
+        let out = comp.command("walk").unwrap();
+        assert_eq!(out, vec![AsciiOutput::Line("walk".to_string()), AsciiOutput::Value(12345)]);
+        assert!(comp.halted);
+        assert_eq!(text(&out), "walk");
+        assert_eq!(values(&out), vec![12345]);
+    }
+
+    #[test]
+    fn test_run_for() {
+        let mut comp = AsciiComp::from_program(0, assemble(ECHO).unwrap());
+        assert_eq!(comp.run_for(2).unwrap(), (vec![], false));
+        assert_eq!(comp.run_for(100).unwrap(), (vec![AsciiOutput::Line("Hi".to_string()), AsciiOutput::Line("?".to_string())], true));
+        comp.send_line("");
+        assert_eq!(comp.run_for(100).unwrap(), (vec![AsciiOutput::Line(String::new()), AsciiOutput::Value(12345)], true));
+        assert!(comp.halted);
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut comp = AsciiComp::from_program(0, assemble(ECHO).unwrap());
+        let start = comp.comp.snapshot();
+        comp.run_for(2).unwrap();
+        comp.restore(&start);
+        assert_eq!(comp.run().unwrap(), vec![AsciiOutput::Line("Hi".to_string()), AsciiOutput::Line("?".to_string())]);
+        comp.command("").unwrap();
+        assert!(comp.halted);
+        comp.restore(&start);
+        assert!(!comp.halted);
+    }
+
+    #[test]
+    fn test_value_splits_line() {
+        let mut comp = AsciiComp::from_program(0, vec![104,65,104,-1,104,66,104,10,99]);
+        assert_eq!(comp.run().unwrap(), vec![
+            AsciiOutput::Line("A".to_string()), AsciiOutput::Value(-1), AsciiOutput::Line("B".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,314 @@
+//! Assembler for writing Intcode programs in mnemonics.
+//!
+//! The syntax is the one `disasm::listing` prints, so a listing assembles
+//! back into the same program:
+//!
+//! ```text
+//! ; compare the input with 8
+//! start:  in -> [x]
+//!         eq [x], #8 -> [x]
+//!         out [x]
+//!         jt #1, #done
+//! x:      data -1
+//! done:   halt
+//! ```
+//!
+//! Operands are `#val` (immediate), `[addr]` (position) and `[rb+off]`
+//! (relative), where values may be numbers, labels or `label+n`. The write
+//! target may be given after `->` or as the last operand. `data v, ...`
+//! emits raw words and `zeros n` reserves `n` zero words. A numeric label
+//! such as `0042:` checks that the next word lands on that address.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::op::{Mode, Op};
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AsmError {
+    pub line: usize, // 1-based source line
+    pub msg: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Num(Numeric),
+    Label(String),
+}
+
+/// A sum of signed terms, resolved once every label is known.
+type Expr = Vec<(Numeric, Term)>;
+
+enum Item {
+    Instr(Op, Vec<(Mode, Expr)>),
+    Data(Vec<Expr>),
+}
+
+fn err<T>(line: usize, msg: String) -> Result<T, AsmError> {
+    Err(AsmError{line, msg})
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => (),
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_expr(line: usize, text: &str) -> Result<Expr, AsmError> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if text.is_empty() {
+        return err(line, "missing value".to_string());
+    }
+    // a whole signed literal, so Numeric::MIN need not be negated
+    if let Ok(n) = text.parse::<Numeric>() {
+        return Ok(vec![(1, Term::Num(n))]);
+    }
+    let mut expr = Vec::new();
+    let mut sign = 1;
+    let mut term = String::new();
+    for (i, c) in text.chars().enumerate() {
+        if (c == '+' || c == '-') && !term.is_empty() {
+            expr.push((sign, parse_term(line, &term)?));
+            term.clear();
+            sign = if c == '-' { -1 } else { 1 };
+        } else if (c == '+' || c == '-') && i == 0 {
+            sign = if c == '-' { -1 } else { 1 };
+        } else {
+            term.push(c);
+        }
+    }
+    expr.push((sign, parse_term(line, &term)?));
+    Ok(expr)
+}
+
+fn parse_term(line: usize, term: &str) -> Result<Term, AsmError> {
+    if let Ok(n) = term.parse::<Numeric>() {
+        Ok(Term::Num(n))
+    } else if is_ident(term) {
+        Ok(Term::Label(term.to_string()))
+    } else {
+        err(line, format!("bad value '{}'", term))
+    }
+}
+
+fn parse_operand(line: usize, text: &str) -> Result<(Mode, Expr), AsmError> {
+    let text = text.trim();
+    if let Some(val) = text.strip_prefix('#') {
+        return Ok((Mode::Immediate, parse_expr(line, val)?));
+    }
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        let inner = inner.trim();
+        if inner == "rb" {
+            return Ok((Mode::Relative, vec![(1, Term::Num(0))]));
+        }
+        if let Some(off) = inner.strip_prefix("rb") {
+            let off = off.trim_start();
+            if off.starts_with('+') || off.starts_with('-') {
+                return Ok((Mode::Relative, parse_expr(line, off)?));
+            }
+        }
+        return Ok((Mode::Position, parse_expr(line, inner)?));
+    }
+    err(line, format!("bad operand '{}', expected #val, [addr] or [rb+off]", text))
+}
+
+fn parse_item(line: usize, text: &str) -> Result<Item, AsmError> {
+    let (word, rest) = match text.find(char::is_whitespace) {
+        Some(i) => (&text[..i], text[i..].trim()),
+        None => (text, ""),
+    };
+    let word = word.to_ascii_lowercase();
+    let fields = || rest.split(',').filter(|f| !f.trim().is_empty());
+    match word.as_str() {
+        "data" => {
+            let vals = fields().map(|f| parse_expr(line, f)).collect::<Result<Vec<_>,_>>()?;
+            if vals.is_empty() {
+                return err(line, "data needs at least one value".to_string());
+            }
+            return Ok(Item::Data(vals));
+        },
+        "zeros" => {
+            return match rest.parse::<usize>() {
+                Ok(n) => Ok(Item::Data(vec![vec![(1, Term::Num(0))]; n])),
+                Err(_) => err(line, format!("bad zeros count '{}'", rest)),
+            };
+        },
+        _ => (),
+    }
+    let op = match Op::ALL.iter().find(|op| op.mnemonic().eq_ignore_ascii_case(&word)) {
+        Some(op) => *op,
+        None => return err(line, format!("unknown mnemonic '{}'", word)),
+    };
+    let (reads, write) = match rest.find("->") {
+        Some(i) => (&rest[..i], Some(&rest[i+2..])),
+        None => (rest, None),
+    };
+    let mut params = Vec::new();
+    for f in reads.split(',').filter(|f| !f.trim().is_empty()) {
+        params.push(parse_operand(line, f)?);
+    }
+    if let Some(w) = write {
+        if !op.writes() {
+            return err(line, format!("{} does not write memory", op.mnemonic()));
+        }
+        params.push(parse_operand(line, w)?);
+    }
+    if params.len() != op.num_params() {
+        return err(line, format!("{} takes {} operands, got {}",
+                                 op.mnemonic(), op.num_params(), params.len()));
+    }
+    if op.writes() && params.last().unwrap().0 == Mode::Immediate {
+        return err(line, format!("{} cannot write to an immediate", op.mnemonic()));
+    }
+    Ok(Item::Instr(op, params))
+}
+
+fn resolve(line: usize, expr: &[(Numeric, Term)], labels: &HashMap<String, usize>) -> Result<Numeric, AsmError> {
+    let mut total: Numeric = 0;
+    for (sign, term) in expr.iter() {
+        let val = match term {
+            Term::Num(n) => *n,
+            Term::Label(l) => match labels.get(l) {
+                Some(addr) => *addr as Numeric,
+                None => return err(line, format!("undefined label '{}'", l)),
+            },
+        };
+        total = match sign.checked_mul(val).and_then(|v| total.checked_add(v)) {
+            Some(t) => t,
+            None => return err(line, "value out of range".to_string()),
+        };
+    }
+    Ok(total)
+}
+
+/// Assemble mnemonic source into a program for `IntcodeComp::new`.
+pub fn assemble(source: &str) -> Result<Vec<Numeric>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut items = Vec::new();
+    let mut addr = 0;
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut text = match raw.find(';') {
+            Some(c) => &raw[..c],
+            None => raw,
+        }.trim();
+        // leading labels
+        while let Some(c) = text.find(':') {
+            let label = text[..c].trim();
+            if let Ok(n) = label.parse::<usize>() {
+                if n != addr {
+                    return err(line, format!("address {} does not match {}", label, addr));
+                }
+            } else if is_ident(label) {
+                if labels.insert(label.to_string(), addr).is_some() {
+                    return err(line, format!("duplicate label '{}'", label));
+                }
+            } else {
+                break;
+            }
+            text = text[c+1..].trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+        let item = parse_item(line, text)?;
+        addr += match &item {
+            Item::Instr(op, _) => 1 + op.num_params(),
+            Item::Data(vals) => vals.len(),
+        };
+        items.push((line, item));
+    }
+
+    let mut program = Vec::new();
+    for (line, item) in items.iter() {
+        match item {
+            Item::Instr(op, params) => {
+                let mut word = op.code();
+                let mut scale = 100;
+                for (mode, _) in params.iter() {
+                    word += *mode as Numeric * scale;
+                    scale *= 10;
+                }
+                program.push(word);
+                for (_, expr) in params.iter() {
+                    program.push(resolve(*line, expr, &labels)?);
+                }
+            },
+            Item::Data(vals) => {
+                for expr in vals.iter() {
+                    program.push(resolve(*line, expr, &labels)?);
+                }
+            },
+        }
+    }
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::listing;
+    use crate::str_to_vec;
+
+    #[test]
+    fn test_assemble() {
+        let src = "
+            ; is the input equal to 8?
+            in -> [x]
+            eq [x], [eight] -> [x]
+            out [x]
+            halt
+        x:      data -1
+        eight:  data 8
+        ";
+        assert_eq!(assemble(src).unwrap(), str_to_vec("3,9,8,9,10,9,4,9,99,-1,8"));
+    }
+
+    #[test]
+    fn test_modes_and_labels() {
+        let src = "
+        start:  arb #buf+2
+                add [rb-1], #5, [rb+1]
+                JT #1, #start
+        buf:    zeros 2
+        ";
+        assert_eq!(assemble(src).unwrap(), vec![109,11,21201,-1,5,1,1105,1,0,0,0]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(assemble("add #1, #2").unwrap_err().msg, "ADD takes 3 operands, got 2");
+        assert_eq!(assemble("in #1").unwrap_err().msg, "IN cannot write to an immediate");
+        assert_eq!(assemble("\nout [nowhere]").unwrap_err(),
+                   AsmError{line: 2, msg: "undefined label 'nowhere'".to_string()});
+        assert_eq!(assemble("frob #1").unwrap_err().msg, "unknown mnemonic 'frob'");
+        assert_eq!(assemble("0001: halt").unwrap_err().msg, "address 0001 does not match 0");
+        assert_eq!(assemble("halt\ndata 9223372036854775807+1").unwrap_err(),
+                   AsmError{line: 2, msg: "value out of range".to_string()});
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let prog = str_to_vec("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+        assert_eq!(assemble(&listing(&prog)).unwrap(), prog);
+        let prog = vec![104, Numeric::MIN, 204, Numeric::MIN, 99, Numeric::MIN];
+        let text = listing(&prog);
+        assert!(text.contains("OUT #-9223372036854775808"));
+        assert!(text.contains("DATA -9223372036854775808"));
+        assert_eq!(assemble(&text).unwrap(), prog);
+        let prog = str_to_vec("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        assert_eq!(assemble(&listing(&prog)).unwrap(), prog);
+    }
+}
@@ -0,0 +1,280 @@
+//! Control-flow graph recovery for Intcode programs.
+//!
+//! The code found by `disasm::find_code` is split into basic blocks at jump
+//! targets, after jumps, and at return addresses. Calls follow the
+//! convention of the puzzle programs: the return address is written to the
+//! stack with two immediate operands right before an unconditional jump, the
+//! callee opens its frame with `ARB #n`, and it returns with a jump through
+//! `[rb+k]`.
+//!
+//! Each block also gets the cells it reads and writes. Position operands
+//! name an absolute address; relative operands are kept as offsets from the
+//! relative base on entry to the block, adjusted for any `ARB #n` inside it.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::disasm::{find_code, format_line, return_address, Line};
+use crate::op::{Instr, Mode, Op, Param};
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum Access {
+    Addr(usize), // position operand
+    Stack(Numeric), // relative operand, offset from rb on entry to the block
+    Unknown, // relative operand after rb was moved by a computed amount
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum EdgeKind {
+    Fallthrough, // the next block in memory
+    Jump, // a static jump target
+    Call, // a static jump that pushed a return address
+    Return, // from a call site to its return address
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Edge {
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Block {
+    pub start: usize,
+    pub instrs: Vec<Instr>,
+    pub succs: Vec<Edge>,
+    pub computed: bool, // ends in a jump whose target is only known at run time
+    pub returns: bool, // ends in a jump through `[rb+k]`
+    pub reads: BTreeSet<Access>,
+    pub writes: BTreeSet<Access>,
+}
+
+impl Block {
+    /// One past the last word of the block.
+    pub fn end(&self) -> usize {
+        self.instrs.last().map_or(self.start, |i| i.addr + i.size())
+    }
+
+    fn link(&mut self, calls: &[Call], starts: &BTreeSet<usize>) {
+        let last = self.instrs.last().unwrap().clone();
+        let next = self.end();
+        let mut edge = |to: usize, kind: EdgeKind| {
+            if starts.contains(&to) {
+                self.succs.push(Edge{to, kind});
+            }
+        };
+        match last.op {
+            Op::Halt => (),
+            Op::Jt | Op::Jf => {
+                if let Some(call) = calls.iter().find(|c| c.site == last.addr) {
+                    edge(call.target, EdgeKind::Call);
+                    edge(call.ret, EdgeKind::Return);
+                } else if let Some(t) = last.static_target() {
+                    edge(t, EdgeKind::Jump);
+                }
+                if !last.is_unconditional() {
+                    edge(next, EdgeKind::Fallthrough);
+                }
+                self.computed = last.static_target().is_none();
+                self.returns = self.computed && last.params[1].mode == Mode::Relative;
+            },
+            _ => edge(next, EdgeKind::Fallthrough),
+        }
+    }
+
+    fn accesses(&mut self) {
+        let mut rb: Option<Numeric> = Some(0);
+        for instr in self.instrs.iter() {
+            let access = |p: &Param| match p.mode {
+                Mode::Position if p.value >= 0 => Some(Access::Addr(p.value as usize)),
+                Mode::Position | Mode::Immediate => None,
+                Mode::Relative => Some(rb.and_then(|d| d.checked_add(p.value)).map_or(Access::Unknown, Access::Stack)),
+            };
+            let (reads, write) = match instr.op.writes() {
+                true => instr.params.split_at(instr.params.len()-1),
+                false => instr.params.split_at(instr.params.len()),
+            };
+            self.reads.extend(reads.iter().filter_map(access));
+            self.writes.extend(write.iter().filter_map(access));
+            if instr.op == Op::Arb {
+                rb = match instr.params[0].mode {
+                    Mode::Immediate => rb.and_then(|d| d.checked_add(instr.params[0].value)),
+                    _ => None,
+                };
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Call {
+    pub site: usize, // address of the jump
+    pub target: usize,
+    pub ret: usize, // return address pushed before the jump
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Cfg {
+    pub blocks: BTreeMap<usize, Block>,
+    pub calls: Vec<Call>,
+}
+
+impl Cfg {
+    pub fn new(program: &[Numeric]) -> Cfg {
+        let code = find_code(program);
+        let mut leaders = BTreeSet::new();
+        leaders.insert(0);
+        let mut calls = Vec::new();
+        for instr in code.values() {
+            if !instr.op.is_jump() {
+                continue;
+            }
+            leaders.insert(instr.addr + instr.size());
+            if let Some(t) = instr.static_target() {
+                leaders.insert(t);
+            }
+            if let Some(ret) = return_address(program, &code, instr) {
+                leaders.insert(ret);
+                if let Some(target) = instr.static_target() {
+                    calls.push(Call{site: instr.addr, target, ret});
+                }
+            }
+        }
+
+        let mut blocks = BTreeMap::new();
+        let mut current: Option<Block> = None;
+        for instr in code.values() {
+            let split = match &current {
+                Some(b) => b.end() != instr.addr || leaders.contains(&instr.addr),
+                None => true,
+            };
+            if split {
+                if let Some(b) = current.take() {
+                    blocks.insert(b.start, b);
+                }
+                current = Some(Block{start: instr.addr, instrs: Vec::new(), succs: Vec::new(), computed: false,
+                                     returns: false, reads: BTreeSet::new(), writes: BTreeSet::new()});
+            }
+            let b = current.as_mut().unwrap();
+            b.instrs.push(instr.clone());
+            if instr.op.is_jump() || instr.op == Op::Halt {
+                blocks.insert(b.start, current.take().unwrap());
+            }
+        }
+        if let Some(b) = current {
+            blocks.insert(b.start, b);
+        }
+
+        let starts: BTreeSet<usize> = blocks.keys().copied().collect();
+        for b in blocks.values_mut() {
+            b.link(&calls, &starts);
+            b.accesses();
+        }
+        Cfg{blocks, calls}
+    }
+
+    /// Addresses that are called, with the frame size the callee opens
+    /// with `ARB #n`, if it does.
+    pub fn functions(&self) -> BTreeMap<usize, Option<Numeric>> {
+        self.calls.iter().map(|c| {
+            let frame = self.blocks.get(&c.target)
+                .and_then(|b| b.instrs.first())
+                .filter(|i| i.op == Op::Arb && i.params[0].mode == Mode::Immediate)
+                .map(|i| i.params[0].value);
+            (c.target, frame)
+        }).collect()
+    }
+
+    /// The graph in Graphviz DOT, one box per block listing its code.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph cfg {{").unwrap();
+        writeln!(out, "    node [shape=box, fontname=monospace];").unwrap();
+        let functions = self.functions();
+        for b in self.blocks.values() {
+            let mut label = String::new();
+            if functions.contains_key(&b.start) {
+                write!(label, "fn_{}:\\l", b.start).unwrap();
+            }
+            for i in b.instrs.iter() {
+                write!(label, "{}\\l", format_line(&Line::Code(i.clone())).replace('"', "\\\"")).unwrap();
+            }
+            writeln!(out, "    b{} [label=\"{}\"];", b.start, label).unwrap();
+        }
+        for b in self.blocks.values() {
+            for e in b.succs.iter() {
+                let style = match e.kind {
+                    EdgeKind::Fallthrough => "",
+                    EdgeKind::Jump => " [label=\"jump\"]",
+                    EdgeKind::Call => " [label=\"call\", style=bold]",
+                    EdgeKind::Return => " [label=\"ret\", style=dashed]",
+                };
+                writeln!(out, "    b{} -> b{}{};", b.start, e.to, style).unwrap();
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::str_to_vec;
+
+    #[test]
+    fn test_call_return() {
+        // push return address 9 to [rb+0], call 10, which returns via [rb+0]
+        let prog = str_to_vec("109,20,21101,0,9,0,1105,1,10,99,2106,0,0");
+        let cfg = Cfg::new(&prog);
+        assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 9, 10]);
+        assert_eq!(cfg.calls, vec![Call{site: 6, target: 10, ret: 9}]);
+        assert_eq!(cfg.blocks[&0].succs, vec![Edge{to: 10, kind: EdgeKind::Call}, Edge{to: 9, kind: EdgeKind::Return}]);
+        assert_eq!(cfg.blocks[&0].writes.iter().copied().collect::<Vec<_>>(), vec![Access::Stack(20)]);
+        assert!(cfg.blocks[&9].succs.is_empty());
+        assert!(cfg.blocks[&10].returns);
+        assert_eq!(cfg.blocks[&10].reads.iter().copied().collect::<Vec<_>>(), vec![Access::Stack(0)]);
+        assert_eq!(cfg.functions(), vec![(10, None)].into_iter().collect());
+    }
+
+    #[test]
+    fn test_return_inside_block() {
+        // the call at 9 returns to 14, in the middle of the code at 12
+        let prog = str_to_vec("1005,30,12,109,30,21101,0,14,0,1105,1,17,104,7,104,8,99,2106,0,0,0");
+        let cfg = Cfg::new(&prog);
+        assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 3, 12, 14, 17]);
+        assert_eq!(cfg.blocks[&3].succs, vec![Edge{to: 17, kind: EdgeKind::Call}, Edge{to: 14, kind: EdgeKind::Return}]);
+        assert_eq!(cfg.blocks[&12].succs, vec![Edge{to: 14, kind: EdgeKind::Fallthrough}]);
+    }
+
+    #[test]
+    fn test_branches() {
+        let prog = str_to_vec("3,9,8,9,10,9,1005,9,12,104,0,99,104,1,99");
+        let cfg = Cfg::new(&prog);
+        assert_eq!(cfg.blocks.keys().copied().collect::<Vec<_>>(), vec![0, 9, 12]);
+        let b = &cfg.blocks[&0];
+        assert_eq!(b.succs, vec![Edge{to: 12, kind: EdgeKind::Jump}, Edge{to: 9, kind: EdgeKind::Fallthrough}]);
+        assert_eq!(b.reads.iter().copied().collect::<Vec<_>>(), vec![Access::Addr(9), Access::Addr(10)]);
+        assert_eq!(b.writes.iter().copied().collect::<Vec<_>>(), vec![Access::Addr(9)]);
+        assert_eq!(cfg.blocks[&9].end(), 12);
+    }
+
+    #[test]
+    fn test_stack_overflow() {
+        // rb+1 past the top of Numeric, then rb moved past it
+        let prog = str_to_vec("109,9223372036854775807,204,1,109,1,204,0,99");
+        let cfg = Cfg::new(&prog);
+        assert_eq!(cfg.blocks[&0].reads.iter().copied().collect::<Vec<_>>(), vec![Access::Unknown]);
+    }
+
+    #[test]
+    fn test_dot() {
+        let prog = str_to_vec("109,20,21101,0,9,0,1105,1,10,99,2106,0,0");
+        let dot = Cfg::new(&prog).to_dot();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    b0 -> b10 [label=\"call\", style=bold];\n"));
+        assert!(dot.contains("    b10 [label=\"fn_10:\\l0010: JF #0, [rb+0]\\l\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
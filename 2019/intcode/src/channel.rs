@@ -0,0 +1,181 @@
+//! Tokio channel adapter around the synchronous VM.
+
+use tokio::sync::mpsc;
+
+use crate::comp::{Event, IntcodeComp};
+use crate::debugger::Debugger;
+use crate::error::IntcodeError;
+use crate::Numeric;
+
+pub struct IO {
+    pub tx: Option<mpsc::Sender<Numeric>>, // send on this channel
+    pub tx_signal: Option<mpsc::Receiver<bool>>, // request sends come in here
+    pub rx: Option<mpsc::Receiver<Numeric>>, // receive on this channel
+    pub rx_signal: Option<mpsc::Sender<bool>>, // request sends originate here
+    pub history: Vec<Numeric>, // history of what was sent
+}
+
+impl IO {
+    /// An IO that loops its own output back to its input.
+    pub fn new() -> IO {
+        let (s, r) = mpsc::channel(100);
+        IO{tx: Some(s), tx_signal: None, rx: Some(r), rx_signal: None, history: Vec::new()}
+    }
+
+    /// A looped-back IO with `input` already queued.
+    pub async fn from(input: Numeric) -> IO {
+        let mut io = IO::new();
+        io.send_output(input).await;
+        io
+    }
+
+    pub fn get_history_last(&self) -> Option<Numeric> {
+        self.history.last().copied()
+    }
+
+    /// The next input, or `None` if there is no input channel or it closed.
+    pub async fn get_input(&mut self) -> Option<Numeric> {
+        if let Some(t) = &mut self.rx_signal {
+            if t.send(true).await.is_err() {
+                println!("WARN: get_input failed");
+            }
+        }
+        match &mut self.rx {
+            Some(r) => r.recv().await,
+            None => None,
+        }
+    }
+
+    pub async fn await_send_output(&mut self) -> Option<()> {
+        match &mut self.tx_signal {
+            Some(r) => r.recv().await.map(|_| ()),
+            None => None,
+        }
+    }
+
+    /// Send a value, or `None` if there is no output channel. A receiver
+    /// that has gone away is only warned about, since a machine may keep
+    /// talking after its peer halted.
+    pub async fn send_output(&mut self, out: Numeric) -> Option<()> {
+        self.history.push(out);
+        match &mut self.tx {
+            Some(t) => if t.send(out).await.is_err() {
+                println!("WARN: send_output failed");
+            },
+            None => return None,
+        }
+        Some(())
+    }
+}
+
+impl Default for IO {
+    fn default() -> IO {
+        IO::new()
+    }
+}
+
+/// An `IntcodeComp` whose input and output are wired to an `IO`.
+pub struct ChannelComp {
+    pub comp: IntcodeComp, // the machine being driven
+    pub io: IO, // io
+    pub debugger: Option<Debugger>, // stops the machine for commands if set
+}
+
+impl ChannelComp {
+    pub fn new(name: usize, instrs: Vec<Numeric>, io: IO) -> ChannelComp {
+        ChannelComp{comp: IntcodeComp::new(name, instrs), io, debugger: None}
+    }
+
+    /// Stop for commands under `debugger`, e.g. one from `Debugger::from_env`.
+    /// Its prompt blocks the thread running `run`.
+    pub fn with_debugger(mut self, debugger: Debugger) -> ChannelComp {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    /// Run until the program halts, returning the last value sent.
+    pub async fn run(&mut self) -> Result<Option<Numeric>, IntcodeError> {
+        loop {
+            let event = match &mut self.debugger {
+                Some(d) => d.run_until_event(&mut self.comp)?,
+                None => self.comp.run_until_event()?,
+            };
+            match event {
+                Event::NeedsInput => match self.io.get_input().await {
+                    Some(val) => self.comp.push_input(val),
+                    None => return Err(IntcodeError::ChannelClosed(self.comp.location())),
+                },
+                Event::Output(val) => if self.io.send_output(val).await.is_none() {
+                    return Err(IntcodeError::ChannelClosed(self.comp.location()));
+                },
+                Event::Halted => break,
+            }
+        }
+        Ok(self.io.get_history_last())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_test::block_on;
+
+    #[test]
+    fn test_channel_comp_loopback() {
+        async fn t() {
+            let v = vec![3,9,8,9,10,9,4,9,99,-1,8];
+            let io = IO::from(8).await;
+            let mut comp = ChannelComp::new(0, v, io);
+            assert_eq!(comp.run().await, Ok(Some(1)));
+            assert_eq!(comp.io.history, vec![8, 1]);
+        }
+        block_on(t());
+    }
+
+    #[test]
+    fn test_channel_comp_pair() {
+        async fn t() {
+            // the first machine doubles its input and feeds the second
+            let (s1, r1) = mpsc::channel(10);
+            let (mut s0, r0) = mpsc::channel(10);
+            let (s2, mut r2) = mpsc::channel(10);
+            s0.send(21).await.unwrap();
+            let io_a = IO{tx: Some(s1), tx_signal: None, rx: Some(r0), rx_signal: None, history: Vec::new()};
+            let io_b = IO{tx: Some(s2), tx_signal: None, rx: Some(r1), rx_signal: None, history: Vec::new()};
+            let mut a = ChannelComp::new(0, vec![3,9,1002,9,2,9,4,9,99], io_a);
+            let mut b = ChannelComp::new(1, vec![3,9,1001,9,1,9,4,9,99], io_b);
+            let (x, y) = tokio::join!(a.run(), b.run());
+            assert_eq!(x, Ok(Some(42)));
+            assert_eq!(y, Ok(Some(43)));
+            assert_eq!(r2.recv().await, Some(43));
+        }
+        block_on(t());
+    }
+
+    #[test]
+    fn test_channel_comp_debugger() {
+        async fn t() {
+            // patch the comparison constant before the program reads it
+            let v = vec![3,9,8,9,10,9,4,9,99,-1,8];
+            let io = IO::from(5).await;
+            let script = std::io::Cursor::new(b"set 10 5\nc\n".to_vec());
+            let mut comp = ChannelComp::new(0, v, io).with_debugger(Debugger::with_io(10, Box::new(script), Box::new(std::io::sink())));
+            assert_eq!(comp.run().await, Ok(Some(1)));
+            assert_eq!(comp.debugger.unwrap().history.len(), 3);
+        }
+        block_on(t());
+    }
+
+    #[test]
+    fn test_channel_closed() {
+        async fn t() {
+            let (s, r) = mpsc::channel(10);
+            drop(s);
+            let io = IO{tx: None, tx_signal: None, rx: Some(r), rx_signal: None, history: Vec::new()};
+            let mut comp = ChannelComp::new(3, vec![3,0,99], io);
+            let err = comp.run().await.unwrap_err();
+            assert_eq!(err.to_string(), "machine 3 at 0 (instr 3): channel closed");
+        }
+        block_on(t());
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::VecDeque;
+
+use crate::error::{IntcodeError, Location};
+use crate::memory::{Memory, MemoryError, MemoryKind};
+use crate::op::Op;
+use crate::profile::{Profiler, REPORT_TOP};
+use crate::trace::Tracer;
+use crate::word::Word;
+use crate::Numeric;
+
+/// Why the VM stopped in `run_until_event`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Event<W = Numeric> {
+    NeedsInput, // an input instruction found the queue empty
+    Output(W), // an output instruction produced a value
+    Halted, // hit instruction 99
+}
+
+/// An Intcode machine computing in words of type `W`; see `word`.
+#[derive(Clone)]
+pub struct IntcodeComp<W: Word = Numeric> {
+    pub name: usize, // name of computer
+    pub instrs: Memory<W>, // instructions / memory
+    pub input: VecDeque<W>, // pending input values
+    pub ptr: usize, // current instruction pointer
+    pub rel_base: Numeric, // relative base for mode 2
+    pub tracer: Tracer, // execution trace; console level from the environment
+    pub profiler: Option<Profiler>, // execution counts, if profiling
+}
+
+impl IntcodeComp {
+    pub fn new(name: usize, instrs: Vec<Numeric>) -> IntcodeComp {
+        IntcodeComp::with_memory(name, Memory::dense(instrs))
+    }
+
+    /// A machine on a chosen memory backend, e.g. `Memory::paged(&prog)`.
+    pub fn with_memory(name: usize, instrs: Memory) -> IntcodeComp {
+        IntcodeComp::from_memory(name, instrs)
+    }
+
+    /// Split an instruction into its three parameter modes.
+    pub fn get_modes(instr: Numeric) -> (Numeric,Numeric,Numeric) {
+        (instr % 1000 / 100,
+         instr % 10000 / 1000,
+         instr % 100000 / 10000,
+        )
+    }
+
+    /// Where the instruction at the pointer writes, if it writes at all,
+    /// read from the instruction word the way the interpreter reads it:
+    /// digits `Instr::decode` rejects still leave the write in place.
+    pub(crate) fn write_target(&self) -> Option<Numeric> {
+        let instr = self.instrs.get(self.ptr)?;
+        let op = Op::from_code(instr % 100)?;
+        if !op.writes() {
+            return None;
+        }
+        let n = op.num_params();
+        let modes = IntcodeComp::get_modes(instr);
+        let arg = self.instrs.get(self.ptr + n)?;
+        match [modes.0, modes.1, modes.2][n-1] {
+            2 => self.rel_base.checked_add(arg),
+            _ => Some(arg),
+        }
+    }
+}
+
+impl<W: Word> IntcodeComp<W> {
+    /// A machine of any word type, e.g. `IntcodeComp::<i128>::from_words`.
+    pub fn from_words(name: usize, instrs: Vec<W>) -> IntcodeComp<W> {
+        IntcodeComp::from_memory(name, Memory::from_words(MemoryKind::Dense, &instrs))
+    }
+
+    pub fn from_memory(name: usize, instrs: Memory<W>) -> IntcodeComp<W> {
+        IntcodeComp{name, instrs, input: VecDeque::new(), ptr: 0, rel_base: 0,
+                    tracer: Tracer::console(), profiler: Profiler::from_env()}
+    }
+
+    /// Trace with `tracer`, e.g. one writing a file from `Tracer::from_env`.
+    pub fn with_tracer(mut self, tracer: Tracer) -> IntcodeComp<W> {
+        self.tracer = tracer;
+        self
+    }
+
+    /// Count executions and memory accesses, and report the hot spots when
+    /// the machine halts; see `Profiler`.
+    pub fn with_profiler(mut self) -> IntcodeComp<W> {
+        self.profiler = Some(Profiler::reporting(REPORT_TOP));
+        self
+    }
+
+    /// Queue a value for the next input instruction.
+    pub fn push_input(&mut self, val: W) {
+        self.input.push_back(val);
+    }
+
+    /// Where the machine is now, for error reports.
+    pub fn location(&self) -> Location {
+        let instr = self.instrs.get(self.ptr).and_then(|w| w.to_i64()).unwrap_or(0);
+        Location{name: self.name, ptr: self.ptr, instr}
+    }
+
+    /// Execute a single instruction.
+    ///
+    /// Returns `None` for instructions that only touch memory or the
+    /// instruction pointer. On `NeedsInput` and `Halted` the pointer is left
+    /// in place, so the same instruction runs again on the next call. A
+    /// faulty instruction leaves the machine as it was before the step,
+    /// apart from any memory it wrote.
+    pub fn step(&mut self) -> Result<Option<Event<W>>, IntcodeError> {
+        let ptr = self.ptr;
+        let result = self.execute();
+        match result {
+            Err(_) => self.tracer.abort(),
+            // a halt is counted by the profiler, once
+            Ok(Some(Event::NeedsInput)) | Ok(Some(Event::Halted)) => (),
+            Ok(_) => if let Some(p) = &mut self.profiler {
+                if let Some(op) = self.instrs[ptr].to_i64().and_then(|i| Op::from_code(i % 100)) {
+                    p.executed(ptr, op);
+                }
+            },
+        }
+        result
+    }
+
+    fn execute(&mut self) -> Result<Option<Event<W>>, IntcodeError> {
+        if self.ptr >= self.instrs.len() {
+            return Err(IntcodeError::EndOfMemory(self.location()));
+        }
+        let instr = match self.instrs[self.ptr].to_i64() {
+            Some(i) => i,
+            None => return Err(IntcodeError::UnknownOp(self.location())),
+        };
+        let modes = IntcodeComp::get_modes(instr);
+        if self.tracer.is_active() {
+            self.tracer.begin(self.name, &self.instrs, self.ptr, self.rel_base);
+        }
+        match instr%100 {
+            1 => { // add
+                self.ptr = self.three_instr(modes, |x,y| x.checked_add(y))?;
+            },
+            2 => { // multiply
+                self.ptr = self.three_instr(modes, |x,y| x.checked_mul(y))?;
+            },
+            3 => { // input
+                let val = match self.input.front() {
+                    Some(x) => x.clone(),
+                    None => {
+                        self.tracer.abort();
+                        return Ok(Some(Event::NeedsInput));
+                    },
+                };
+                self.tracer.input(&val);
+                self.store(self.ptr+1, modes.0, val)?;
+                self.input.pop_front();
+                self.ptr += 2;
+            },
+            4 => { // output
+                let val = self.load(self.ptr+1, modes.0)?;
+                self.tracer.output(&val);
+                self.tracer.end();
+                self.ptr += 2;
+                return Ok(Some(Event::Output(val)));
+            },
+            5 => { // jump-if-true
+                self.ptr = self.jump_instr(modes, |x,y| {
+                    if !x.is_zero() {
+                        Some(y)
+                    } else {
+                        None
+                    }
+                })?;
+            },
+            6 => { // jump-if-false
+                self.ptr = self.jump_instr(modes, |x,y| {
+                    if x.is_zero() {
+                        Some(y)
+                    } else {
+                        None
+                    }
+                })?;
+            },
+            7 => { // less-than
+                self.ptr = self.three_instr(modes, |x,y| Some(W::from_i64((x < y) as Numeric)))?;
+            },
+            8 => { // equals
+                self.ptr = self.three_instr(modes, |x,y| Some(W::from_i64((x == y) as Numeric)))?;
+            },
+            9 => { // set relative base
+                let val = self.load(self.ptr+1, modes.0)?;
+                self.rel_base = val.to_i64().and_then(|v| self.rel_base.checked_add(v))
+                    .ok_or_else(|| IntcodeError::Overflow(self.location()))?;
+                self.ptr += 2;
+            },
+            99 => {
+                if self.tracer.halted() {
+                    self.tracer.abort();
+                } else {
+                    self.tracer.end();
+                    self.tracer.halt(self.name);
+                }
+                if let Some(p) = &mut self.profiler {
+                    p.halt(self.name, self.ptr, &self.instrs);
+                }
+                return Ok(Some(Event::Halted));
+            },
+            _ => return Err(IntcodeError::UnknownOp(self.location())),
+        };
+        self.tracer.end();
+        Ok(None)
+    }
+
+    /// Step until the program needs input, produces output, or halts.
+    pub fn run_until_event(&mut self) -> Result<Event<W>, IntcodeError> {
+        loop {
+            if let Some(e) = self.step()? {
+                return Ok(e);
+            }
+        }
+    }
+
+    /// Run to halt on the queued input, returning everything output.
+    pub fn run_to_halt(&mut self) -> Result<Vec<W>, IntcodeError> {
+        let mut output = Vec::new();
+        loop {
+            match self.run_until_event()? {
+                Event::Output(x) => output.push(x),
+                Event::NeedsInput => return Err(IntcodeError::NoInput(self.location())),
+                Event::Halted => return Ok(output),
+            }
+        }
+    }
+
+    /// Write memory, growing it as needed.
+    pub fn set_mem(&mut self, index: usize, val: W) -> Result<(), MemoryError> {
+        self.instrs.write(index as Numeric, val)
+    }
+
+    /// Read memory; anything past the end reads as zero.
+    pub fn get_mem(&self, index: usize) -> Result<W, MemoryError> {
+        self.instrs.read(index as Numeric)
+    }
+
+    fn read(&self, addr: Numeric) -> Result<W, IntcodeError> {
+        self.instrs.read(addr).map_err(|e| IntcodeError::Memory(self.location(), e))
+    }
+
+    /// A word used as an address, offset by the relative base in mode 2.
+    fn address(&self, word: &W, relative: bool) -> Result<Numeric, IntcodeError> {
+        let addr = match word.to_i64() {
+            Some(a) => a,
+            None => {
+                let e = match *word < W::from_i64(0) {
+                    true => MemoryError::Negative(Numeric::MIN),
+                    false => MemoryError::OutOfBounds{addr: Numeric::MAX, limit: self.instrs.limit},
+                };
+                return Err(IntcodeError::Memory(self.location(), e));
+            },
+        };
+        match relative {
+            true => self.rel_base.checked_add(addr).ok_or_else(|| IntcodeError::Overflow(self.location())),
+            false => Ok(addr),
+        }
+    }
+
+    fn load(&mut self, index: usize, mode: Numeric) -> Result<W, IntcodeError> {
+        let word = self.read(index as Numeric)?;
+        let addr = match mode {
+            0 => self.address(&word, false)?,
+            1 => return Ok(word),
+            2 => self.address(&word, true)?,
+            x => return Err(IntcodeError::BadMode(self.location(), x)),
+        };
+        if let Some(p) = &mut self.profiler {
+            p.read(addr);
+        }
+        self.read(addr)
+    }
+
+    fn store(&mut self, index: usize, mode: Numeric, val: W) -> Result<(), IntcodeError> {
+        let word = self.read(index as Numeric)?;
+        let pos = match mode {
+            0 => self.address(&word, false)?,
+            1 => return Err(IntcodeError::ImmediateWrite(self.location())),
+            2 => self.address(&word, true)?,
+            x => return Err(IntcodeError::BadMode(self.location(), x)),
+        };
+        let traced = match self.tracer.is_active() {
+            true => Some(val.clone()),
+            false => None,
+        };
+        if let Err(e) = self.instrs.write(pos, val) {
+            return Err(IntcodeError::Memory(self.location(), e));
+        }
+        if let Some(val) = traced {
+            self.tracer.write(pos as usize, &val);
+        }
+        if let Some(p) = &mut self.profiler {
+            p.write(pos as usize);
+        }
+        Ok(())
+    }
+
+    fn three_instr<F>(&mut self, modes: (Numeric,Numeric,Numeric), op: F) -> Result<usize, IntcodeError> where
+            F: Fn(&W, &W) -> Option<W> {
+        let val1 = self.load(self.ptr+1, modes.0)?;
+        let val2 = self.load(self.ptr+2, modes.1)?;
+        let val = op(&val1, &val2).ok_or_else(|| IntcodeError::Overflow(self.location()))?;
+        self.store(self.ptr+3, modes.2, val)?;
+        Ok(self.ptr + 4)
+    }
+
+    fn jump_instr<F>(&mut self, modes: (Numeric,Numeric,Numeric), op: F) -> Result<usize, IntcodeError> where
+            F: Fn(&W, W) -> Option<W> {
+        let val1 = self.load(self.ptr+1, modes.0)?;
+        let val2 = self.load(self.ptr+2, modes.1)?;
+        match op(&val1, val2) {
+            Some(x) => {
+                let x = self.address(&x, false)?;
+                match x < 0 {
+                    true => Err(IntcodeError::Memory(self.location(), MemoryError::Negative(x))),
+                    false => Ok(x as usize),
+                }
+            },
+            None => Ok(self.ptr + 3),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_modes() {
+        assert_eq!(IntcodeComp::get_modes(1002),(0,1,0));
+        assert_eq!(IntcodeComp::get_modes(21101),(1,1,2));
+    }
+
+    #[test]
+    fn test_set_mem() {
+        let v = vec![1,2,3,4];
+        let mut comp = IntcodeComp::new(0, v);
+        comp.set_mem(100, 10).unwrap();
+        assert_eq!(comp.instrs[100], 10);
+        assert_eq!(comp.instrs[99], 0);
+    }
+
+    #[test]
+    fn test_get_mem_past_end() {
+        let comp = IntcodeComp::new(0, vec![99]);
+        assert_eq!(comp.get_mem(10), Ok(0));
+        assert_eq!(comp.instrs.len(), 1);
+    }
+
+    #[test]
+    fn test_paged_memory() {
+        // write far past the program without allocating everything below it
+        let prog = vec![1101,2,3,1000000000,4,1000000000,99];
+        let mut comp = IntcodeComp::with_memory(0, Memory::paged(&prog));
+        assert_eq!(comp.run_to_halt(), Ok(vec![5]));
+        assert_eq!(comp.instrs.kind(), crate::memory::MemoryKind::Paged);
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let prog = vec![1101,2,3,1000,99];
+        let mut comp = IntcodeComp::with_memory(7, Memory::dense(prog).with_limit(100));
+        let err = comp.run_to_halt().unwrap_err();
+        let at = Location{name: 7, ptr: 0, instr: 1101};
+        assert_eq!(err, IntcodeError::Memory(at, MemoryError::OutOfBounds{addr: 1000, limit: 100}));
+        assert_eq!(err.to_string(), "machine 7 at 0 (instr 1101): address 1000 is beyond the memory limit of 100");
+    }
+
+    #[test]
+    fn test_errors() {
+        let run = |prog: Vec<Numeric>| IntcodeComp::new(0, prog).run_to_halt().unwrap_err();
+        assert_eq!(run(vec![42]), IntcodeError::UnknownOp(Location{name: 0, ptr: 0, instr: 42}));
+        assert_eq!(run(vec![301,0,0,0]), IntcodeError::BadMode(Location{name: 0, ptr: 0, instr: 301}, 3));
+        assert_eq!(run(vec![11101,1,1,0]), IntcodeError::ImmediateWrite(Location{name: 0, ptr: 0, instr: 11101}));
+        assert_eq!(run(vec![1105,1,-5]).location().ptr, 0);
+        assert_eq!(run(vec![1101,1,1,0]), IntcodeError::EndOfMemory(Location{name: 0, ptr: 4, instr: 0}));
+        assert_eq!(run(vec![104,1,3,0,99]), IntcodeError::NoInput(Location{name: 0, ptr: 2, instr: 3}));
+        assert_eq!(run(vec![4,-1]).to_string(), "machine 0 at 0 (instr 4): negative address -1");
+    }
+
+    #[test]
+    fn test_step_events() {
+        let mut comp = IntcodeComp::new(0, vec![3,0,4,0,99]);
+        assert_eq!(comp.step(), Ok(Some(Event::NeedsInput)));
+        assert_eq!(comp.ptr, 0);
+        comp.push_input(42);
+        assert_eq!(comp.step(), Ok(None));
+        assert_eq!(comp.step(), Ok(Some(Event::Output(42))));
+        assert_eq!(comp.step(), Ok(Some(Event::Halted)));
+        assert_eq!(comp.step(), Ok(Some(Event::Halted)));
+    }
+
+    #[test]
+    fn test_run_until_event() {
+        let mut comp = IntcodeComp::new(0, vec![1101,2,3,9,4,9,3,9,99,0]);
+        assert_eq!(comp.run_until_event(), Ok(Event::Output(5)));
+        assert_eq!(comp.run_until_event(), Ok(Event::NeedsInput));
+        comp.push_input(7);
+        assert_eq!(comp.run_until_event(), Ok(Event::Halted));
+        assert_eq!(comp.instrs[9], 7);
+    }
+}
@@ -0,0 +1,398 @@
+//! Interactive debugger for the Intcode VM.
+//!
+//! `Debugger::run_until_event` is a drop-in for `IntcodeComp::run_until_event`
+//! that stops at breakpoints and watchpoints and hands control to a small
+//! command prompt. `Debugger::from_env` gives one for each machine named in
+//! the `INTCODE_DEBUG` environment variable (a comma-separated list of
+//! machine names, or `all`), for a caller to attach with
+//! `ChannelComp::with_debugger` or `Bus::with_debugger`, as the amplifier
+//! chains and the day 13 drone do. The prompt blocks on stdin, stalling the
+//! thread that runs the machine until it is continued.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::comp::{Event, IntcodeComp};
+use crate::disasm::{format_line, Line};
+use crate::error::IntcodeError;
+use crate::op::{Instr, Op};
+use crate::Numeric;
+
+const HELP: &str = "\
+commands:
+  s, step [n]        execute n instructions (default 1)
+  c, continue        run until a breakpoint or watchpoint
+  b, break <addr|op> break at an address or on an opcode (e.g. `b out`)
+  d, delete <addr|op> remove a breakpoint
+  w, watch <addr>    stop when a memory cell changes
+  unwatch <addr>     remove a watchpoint
+  i, info            list breakpoints and watchpoints
+  r, regs            show ptr, rel_base and pending input
+  x <addr> [n]       examine n memory cells
+  set <addr> <val>   patch a memory cell
+  rb <val>           set the relative base
+  ptr <addr>         move the instruction pointer
+  l, list [addr] [n] disassemble n instructions
+  hist [n]           show the last n executed instructions
+  detach             stop debugging and let the machine run
+";
+
+/// An instruction as it was executed.
+#[derive(Debug, Clone)]
+pub struct Executed {
+    pub ptr: usize,
+    pub rel_base: Numeric,
+    pub instr: Option<Instr>, // None if the word did not decode
+}
+
+pub struct Debugger {
+    pub breakpoints: BTreeSet<usize>, // stop before executing these addresses
+    pub break_ops: HashSet<Op>, // stop before executing these opcodes
+    pub watchpoints: BTreeSet<usize>, // stop after these cells change
+    pub history: VecDeque<Executed>, // most recent instruction last
+    pub history_len: usize,
+    steps_left: Option<usize>, // stop after this many instructions
+    resume_at: Option<usize>, // don't re-break on the instruction we stopped at
+    detached: bool,
+    input: Box<dyn BufRead + Send>,
+    out: Box<dyn Write + Send>,
+}
+
+impl Debugger {
+    /// A debugger on stdin/stdout that stops before the first instruction.
+    pub fn new(history_len: usize) -> Debugger {
+        Debugger::with_io(history_len, Box::new(BufReader::new(io::stdin())), Box::new(io::stdout()))
+    }
+
+    pub fn with_io(history_len: usize, input: Box<dyn BufRead + Send>, out: Box<dyn Write + Send>) -> Debugger {
+        Debugger{
+            breakpoints: BTreeSet::new(),
+            break_ops: HashSet::new(),
+            watchpoints: BTreeSet::new(),
+            history: VecDeque::new(),
+            history_len,
+            steps_left: Some(0),
+            resume_at: None,
+            detached: false,
+            input,
+            out,
+        }
+    }
+
+    /// A debugger for machine `name` if `INTCODE_DEBUG` asks for one.
+    pub fn from_env(name: usize) -> Option<Debugger> {
+        let var = env::var("INTCODE_DEBUG").ok()?;
+        match Debugger::wanted(&var, name) {
+            true => Some(Debugger::new(100)),
+            false => None,
+        }
+    }
+
+    /// Whether `names`, as in `INTCODE_DEBUG`, includes machine `name`.
+    pub fn wanted(names: &str, name: usize) -> bool {
+        names.split(',').any(|x| {
+            let x = x.trim();
+            x == "all" || x.parse::<usize>() == Ok(name)
+        })
+    }
+
+    fn should_stop(&self, comp: &IntcodeComp) -> bool {
+        if self.detached {
+            return false;
+        }
+        if self.steps_left == Some(0) {
+            return true;
+        }
+        if self.resume_at == Some(comp.ptr) {
+            return false;
+        }
+        if self.breakpoints.contains(&comp.ptr) {
+            return true;
+        }
+        match comp.instrs.get(comp.ptr).and_then(|w| Op::from_code(w % 100)) {
+            Some(op) => self.break_ops.contains(&op),
+            None => false,
+        }
+    }
+
+    fn read_cell(comp: &IntcodeComp, addr: usize) -> Numeric {
+        comp.instrs[addr]
+    }
+
+    /// Execute one instruction, recording history and checking watchpoints.
+    pub fn step(&mut self, comp: &mut IntcodeComp) -> Result<Option<Event>, IntcodeError> {
+        let before: Vec<Numeric> = self.watchpoints.iter().map(|a| Debugger::read_cell(comp, *a)).collect();
+        let executed = Executed{ptr: comp.ptr, rel_base: comp.rel_base,
+                                instr: comp.instrs.decode(comp.ptr)};
+        let event = comp.step()?;
+        if event != Some(Event::NeedsInput) && event != Some(Event::Halted) {
+            self.history.push_back(executed);
+            while self.history.len() > self.history_len {
+                self.history.pop_front();
+            }
+        }
+        let watches: Vec<usize> = self.watchpoints.iter().copied().collect();
+        for (addr, old) in watches.iter().zip(before.iter()) {
+            let new = Debugger::read_cell(comp, *addr);
+            if new != *old && !self.detached {
+                self.say(&format!("{}: watchpoint [{}]: {} -> {}", comp.name, addr, old, new));
+                self.steps_left = Some(0);
+            }
+        }
+        Ok(event)
+    }
+
+    /// Like `IntcodeComp::run_until_event`, but stops for commands at
+    /// breakpoints, watchpoints and after single steps.
+    pub fn run_until_event(&mut self, comp: &mut IntcodeComp) -> Result<Event, IntcodeError> {
+        loop {
+            if self.should_stop(comp) {
+                self.prompt(comp);
+                self.resume_at = Some(comp.ptr);
+            }
+            let event = match self.step(comp) {
+                Ok(e) => e,
+                Err(e) => {
+                    if !self.detached {
+                        self.say(&format!("{}", e));
+                    }
+                    return Err(e);
+                },
+            };
+            if let Some(n) = self.steps_left {
+                if n > 0 && event != Some(Event::NeedsInput) {
+                    self.steps_left = Some(n - 1);
+                }
+            }
+            if comp.ptr != self.resume_at.unwrap_or(usize::MAX) {
+                self.resume_at = None;
+            }
+            match event {
+                Some(Event::Halted) => {
+                    if !self.detached {
+                        self.say(&format!("{}: halted at {}", comp.name, comp.ptr));
+                    }
+                    return Ok(Event::Halted);
+                },
+                Some(e) => return Ok(e),
+                None => (),
+            }
+        }
+    }
+
+    fn say(&mut self, msg: &str) {
+        let _ = writeln!(self.out, "{}", msg);
+    }
+
+    fn current(comp: &IntcodeComp, addr: usize) -> String {
+        match comp.instrs.decode(addr) {
+            Some(i) => format_line(&Line::Code(i)),
+            None => format_line(&Line::Data{addr, values: vec![Debugger::read_cell(comp, addr)]}),
+        }
+    }
+
+    /// Read and run commands until one of them resumes execution.
+    fn prompt(&mut self, comp: &mut IntcodeComp) {
+        let here = Debugger::current(comp, comp.ptr);
+        self.say(&format!("{}: stopped at {}", comp.name, here));
+        loop {
+            let _ = write!(self.out, "(icdb {}) ", comp.name);
+            let _ = self.out.flush();
+            let mut line = String::new();
+            match self.input.read_line(&mut line) {
+                Ok(0) | Err(_) => { // no more commands, let it run
+                    self.detached = true;
+                    self.steps_left = None;
+                    return;
+                },
+                Ok(_) => (),
+            }
+            if self.command(comp, line.trim()) {
+                return;
+            }
+        }
+    }
+
+    /// Run one command, returning true if execution should resume.
+    pub fn command(&mut self, comp: &mut IntcodeComp, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let arg = |i: usize| words.get(i).and_then(|w| w.parse::<Numeric>().ok());
+        match words.first().copied().unwrap_or("") {
+            "" => (),
+            "s" | "step" => {
+                self.steps_left = Some(arg(1).unwrap_or(1).max(1) as usize);
+                return true;
+            },
+            "c" | "continue" => {
+                self.steps_left = None;
+                return true;
+            },
+            "detach" => {
+                self.detached = true;
+                self.steps_left = None;
+                return true;
+            },
+            "b" | "break" | "d" | "delete" if words.len() == 2 => {
+                let add = words[0].starts_with('b');
+                if let Some(addr) = arg(1).filter(|a| *a >= 0) {
+                    match add {
+                        true => { self.breakpoints.insert(addr as usize); },
+                        false => { self.breakpoints.remove(&(addr as usize)); },
+                    };
+                } else if let Some(op) = Op::ALL.iter().find(|op| op.mnemonic().eq_ignore_ascii_case(words[1])) {
+                    match add {
+                        true => { self.break_ops.insert(*op); },
+                        false => { self.break_ops.remove(op); },
+                    };
+                } else {
+                    self.say(&format!("bad breakpoint '{}'", words[1]));
+                }
+            },
+            "w" | "watch" | "unwatch" if words.len() == 2 => {
+                match arg(1).filter(|a| *a >= 0) {
+                    Some(addr) if words[0] == "unwatch" => { self.watchpoints.remove(&(addr as usize)); },
+                    Some(addr) => { self.watchpoints.insert(addr as usize); },
+                    None => self.say(&format!("bad address '{}'", words[1])),
+                };
+            },
+            "i" | "info" => {
+                let mut ops: Vec<&str> = self.break_ops.iter().map(|op| op.mnemonic()).collect();
+                ops.sort_unstable();
+                let msg = format!("breakpoints: {:?} {:?}\nwatchpoints: {:?}",
+                                  self.breakpoints, ops, self.watchpoints);
+                self.say(&msg);
+            },
+            "r" | "regs" => {
+                let msg = format!("ptr = {}, rel_base = {}, input = {:?}", comp.ptr, comp.rel_base, comp.input);
+                self.say(&msg);
+            },
+            "x" if arg(1).is_some_and(|a| a >= 0) => {
+                let addr = arg(1).unwrap() as usize;
+                let n = arg(2).unwrap_or(1).max(1) as usize;
+                let vals: Vec<String> = (addr..addr.saturating_add(n)).map(|a| Debugger::read_cell(comp, a).to_string()).collect();
+                self.say(&format!("[{}]: {}", addr, vals.join(", ")));
+            },
+            "set" if arg(1).is_some_and(|a| a >= 0) && arg(2).is_some() => {
+                if let Err(e) = comp.set_mem(arg(1).unwrap() as usize, arg(2).unwrap()) {
+                    self.say(&e.to_string());
+                }
+            },
+            "rb" if arg(1).is_some() => comp.rel_base = arg(1).unwrap(),
+            "ptr" if arg(1).is_some_and(|a| a >= 0) => {
+                comp.ptr = arg(1).unwrap() as usize;
+                self.say(&format!("now at {}", Debugger::current(comp, comp.ptr)));
+            },
+            "l" | "list" => {
+                let mut addr = arg(1).map(|a| a.max(0) as usize).unwrap_or(comp.ptr);
+                for _ in 0..arg(2).unwrap_or(10).max(1) {
+                    let line = Debugger::current(comp, addr);
+                    addr += comp.instrs.decode(addr).map_or(1, |i| i.size());
+                    self.say(&line);
+                }
+            },
+            "hist" => {
+                let n = arg(1).map_or(self.history.len(), |n| n.max(0) as usize);
+                let skip = self.history.len().saturating_sub(n);
+                let lines: Vec<String> = self.history.iter().skip(skip).map(|e| {
+                    let text = match &e.instr {
+                        Some(i) => format_line(&Line::Code(i.clone())),
+                        None => format!("{:04}: ???", e.ptr),
+                    };
+                    format!("{}  (rb={})", text, e.rel_base)
+                }).collect();
+                for l in lines {
+                    self.say(&l);
+                }
+            },
+            "h" | "help" => self.say(HELP),
+            _ => self.say(&format!("unknown command '{}', try `help`", line)),
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use std::io::Cursor;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn debugger(script: &str) -> (Debugger, Shared) {
+        let out = Shared(Arc::new(Mutex::new(Vec::new())));
+        let input = Box::new(Cursor::new(script.to_string().into_bytes()));
+        (Debugger::with_io(4, input, Box::new(out.clone())), out)
+    }
+
+    fn text(out: &Shared) -> String {
+        String::from_utf8(out.0.lock().unwrap().clone()).unwrap()
+    }
+
+    const COUNTER: &str = "
+        loop:   add [n], #1 -> [n]
+                out [n]
+                lt [n], #3 -> [t]
+                jt [t], #loop
+                halt
+        n:      data 0
+        t:      data 0
+    ";
+
+    #[test]
+    fn test_breakpoint_and_patch() {
+        let prog = assemble(COUNTER).unwrap();
+        let mut comp = IntcodeComp::new(0, prog);
+        // stop at the start, break on output, then patch the counter
+        let (mut dbg, out) = debugger("b out\nc\nx 14\nset 14 10\nc\n");
+        assert_eq!(dbg.run_until_event(&mut comp), Ok(Event::Output(10)));
+        assert_eq!(dbg.run_until_event(&mut comp), Ok(Event::Halted));
+        let t = text(&out);
+        assert!(t.contains("0: stopped at 0000: ADD [14], #1 -> [14]"));
+        assert!(t.contains("0: stopped at 0004: OUT [14]"));
+        assert!(t.contains("[14]: 1"));
+    }
+
+    #[test]
+    fn test_watchpoint_and_history() {
+        let prog = assemble(COUNTER).unwrap();
+        let mut comp = IntcodeComp::new(0, prog);
+        let (mut dbg, out) = debugger("w 15\nc\nhist 2\nr\ndetach\n");
+        assert_eq!(dbg.run_until_event(&mut comp), Ok(Event::Output(1)));
+        assert_eq!(dbg.run_until_event(&mut comp), Ok(Event::Output(2)));
+        let t = text(&out);
+        assert!(t.contains("watchpoint [15]: 0 -> 1"));
+        assert!(t.contains("0006: LT [14], #3 -> [15]  (rb=0)"));
+        assert!(t.contains("ptr = 10, rel_base = 0"));
+        assert!(dbg.history.len() <= 4);
+    }
+
+    #[test]
+    fn test_step_and_rel_base() {
+        let mut comp = IntcodeComp::new(3, assemble("arb #5\nout [rb+0]\nhalt").unwrap());
+        let (mut dbg, out) = debugger("s\nrb 4\ns 5\n");
+        assert_eq!(dbg.run_until_event(&mut comp), Ok(Event::Output(99)));
+        assert!(text(&out).contains("3: stopped at 0002: OUT [rb+0]"));
+        assert_eq!(comp.rel_base, 4);
+    }
+
+    #[test]
+    fn test_wanted() {
+        assert!(Debugger::wanted("1, 3", 3));
+        assert!(!Debugger::wanted("1,3", 2));
+        assert!(Debugger::wanted("all", 7));
+        assert!(!Debugger::wanted("", 0));
+    }
+}
@@ -0,0 +1,318 @@
+//! Peripherals attached to a machine's input and output.
+//!
+//! A `Bus` runs an `IntcodeComp` and connects it to any number of
+//! `Device`s. When the machine wants input, each device is asked in turn
+//! until one has a value; every output goes to every device. Devices only
+//! see single words, so ones that speak in tuples (a screen's `x, y, tile`)
+//! gather them themselves.
+//!
+//! To read a device back after the run, attach it as an `Rc<RefCell<_>>` and
+//! keep a clone:
+//!
+//! ```text
+//! let screen = Rc::new(RefCell::new(Screen::new()));
+//! let mut bus = Bus::new(comp);
+//! bus.attach(screen.clone());
+//! bus.attach(Joystick::new());
+//! bus.run()?;
+//! println!("{}", screen.borrow().render(|t| if t == 0 { ' ' } else { '#' }));
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use crate::comp::{Event, IntcodeComp};
+use crate::debugger::Debugger;
+use crate::error::IntcodeError;
+use crate::Numeric;
+
+pub trait Device {
+    /// A value for the machine's input, or `None` to pass.
+    fn input(&mut self) -> Option<Numeric> {
+        None
+    }
+
+    /// A value the machine output.
+    fn output(&mut self, _val: Numeric) {}
+
+    /// A value some device gave the machine as input.
+    fn observe_input(&mut self, _val: Numeric) {}
+
+    /// The machine halted.
+    fn halt(&mut self) {}
+}
+
+impl<D: Device> Device for Rc<RefCell<D>> {
+    fn input(&mut self) -> Option<Numeric> {
+        self.borrow_mut().input()
+    }
+
+    fn output(&mut self, val: Numeric) {
+        self.borrow_mut().output(val)
+    }
+
+    fn observe_input(&mut self, val: Numeric) {
+        self.borrow_mut().observe_input(val)
+    }
+
+    fn halt(&mut self) {
+        self.borrow_mut().halt()
+    }
+}
+
+pub struct Bus {
+    pub comp: IntcodeComp,
+    pub debugger: Option<Debugger>, // stops the machine for commands if set
+    devices: Vec<Box<dyn Device>>,
+}
+
+impl Bus {
+    pub fn new(comp: IntcodeComp) -> Bus {
+        Bus{comp, debugger: None, devices: Vec::new()}
+    }
+
+    /// Stop for commands under `debugger`, e.g. one from `Debugger::from_env`.
+    pub fn with_debugger(mut self, debugger: Debugger) -> Bus {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    /// Attach a device; earlier devices get the first say on input.
+    pub fn attach<D: Device + 'static>(&mut self, device: D) {
+        self.devices.push(Box::new(device));
+    }
+
+    /// Ask the devices for input, telling them all what was given.
+    fn feed(&mut self) -> Option<Numeric> {
+        let val = self.devices.iter_mut().find_map(|d| d.input())?;
+        for d in self.devices.iter_mut() {
+            d.observe_input(val);
+        }
+        Some(val)
+    }
+
+    /// Run until the machine halts. Fails with `NoInput` if the machine
+    /// wants input and no device has any.
+    pub fn run(&mut self) -> Result<(), IntcodeError> {
+        loop {
+            let event = match &mut self.debugger {
+                Some(d) => d.run_until_event(&mut self.comp)?,
+                None => self.comp.run_until_event()?,
+            };
+            match event {
+                Event::Output(val) => {
+                    for d in self.devices.iter_mut() {
+                        d.output(val);
+                    }
+                },
+                Event::NeedsInput => match self.feed() {
+                    Some(val) => self.comp.push_input(val),
+                    None => return Err(IntcodeError::NoInput(self.comp.location())),
+                },
+                Event::Halted => {
+                    for d in self.devices.iter_mut() {
+                        d.halt();
+                    }
+                    return Ok(());
+                },
+            }
+        }
+    }
+}
+
+/// Answers every input request from a closure, e.g. the colour of the
+/// panel under a robot.
+pub struct Camera<F: FnMut() -> Numeric> {
+    look: F,
+}
+
+impl<F: FnMut() -> Numeric> Camera<F> {
+    pub fn new(look: F) -> Camera<F> {
+        Camera{look}
+    }
+}
+
+impl<F: FnMut() -> Numeric> Device for Camera<F> {
+    fn input(&mut self) -> Option<Numeric> {
+        Some((self.look)())
+    }
+}
+
+/// Draws `x, y, tile` output triples.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Screen {
+    pub pixels: HashMap<(Numeric,Numeric), Numeric>, // (x, y) to tile
+    partial: Vec<Numeric>, // output not yet forming a whole triple
+}
+
+impl Screen {
+    pub fn new() -> Screen {
+        Screen::default()
+    }
+
+    pub fn get(&self, x: Numeric, y: Numeric) -> Option<Numeric> {
+        self.pixels.get(&(x, y)).copied()
+    }
+
+    /// The smallest rectangle of pixels at non-negative coordinates, one
+    /// line per row, with unset pixels drawn as tile 0. Negative
+    /// coordinates are off screen, like the `(-1, 0)` score display.
+    pub fn render<P: Fn(Numeric) -> char>(&self, palette: P) -> String {
+        let on_screen = || self.pixels.keys().filter(|(x, y)| *x >= 0 && *y >= 0);
+        let width = on_screen().map(|p| p.0 + 1).max().unwrap_or(0);
+        let height = on_screen().map(|p| p.1 + 1).max().unwrap_or(0);
+        let mut out = String::new();
+        for y in 0..height {
+            for x in 0..width {
+                out.push(palette(self.get(x, y).unwrap_or(0)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Device for Screen {
+    fn output(&mut self, val: Numeric) {
+        self.partial.push(val);
+        if self.partial.len() == 3 {
+            self.pixels.insert((self.partial[0], self.partial[1]), self.partial[2]);
+            self.partial.clear();
+        }
+    }
+}
+
+/// A joystick held in `position` (-1 left, 0 neutral, 1 right), or playing
+/// back a list of moves first.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Joystick {
+    pub position: Numeric,
+    pub moves: VecDeque<Numeric>, // played back in order before `position`
+}
+
+impl Joystick {
+    pub fn new() -> Joystick {
+        Joystick::default()
+    }
+}
+
+impl Device for Joystick {
+    fn input(&mut self) -> Option<Numeric> {
+        Some(self.moves.pop_front().unwrap_or(self.position))
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Record {
+    In(Numeric),
+    Out(Numeric),
+    Halt,
+}
+
+/// Keeps a log of everything that crossed the bus.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Recorder {
+    pub log: Vec<Record>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    pub fn outputs(&self) -> Vec<Numeric> {
+        self.log.iter().filter_map(|r| match r {
+            Record::Out(v) => Some(*v),
+            _ => None,
+        }).collect()
+    }
+}
+
+impl Device for Recorder {
+    fn output(&mut self, val: Numeric) {
+        self.log.push(Record::Out(val));
+    }
+
+    fn observe_input(&mut self, val: Numeric) {
+        self.log.push(Record::In(val));
+    }
+
+    fn halt(&mut self) {
+        self.log.push(Record::Halt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    // reads a move, then draws the tile under the cursor after moving it
+    const PAD: &str = "
+    loop:   in -> [d]
+            eq [d], #9 -> [t]
+            jt [t], #done
+            add [x], [d] -> [x]
+            out [x]
+            out #0
+            out #2
+            jt #1, #loop
+    done:   out #-1
+            out #0
+            out [x]
+            halt
+    d:      data 0
+    t:      data 0
+    x:      data 1
+    ";
+
+    #[test]
+    fn test_bus() {
+        let comp = IntcodeComp::new(0, assemble(PAD).unwrap());
+        let screen = Rc::new(RefCell::new(Screen::new()));
+        let recorder = Rc::new(RefCell::new(Recorder::new()));
+        let mut bus = Bus::new(comp);
+        bus.attach(screen.clone());
+        bus.attach(Joystick{position: 9, moves: vec![1, 1, -1].into()});
+        bus.attach(recorder.clone());
+        assert_eq!(bus.run(), Ok(()));
+
+        let screen = screen.borrow();
+        assert_eq!(screen.get(-1, 0), Some(2));
+        assert_eq!(screen.render(|t| if t == 2 { '#' } else { '.' }), "..##\n");
+        let log = &recorder.borrow().log;
+        assert_eq!(&log[..4], &[Record::In(1), Record::Out(2), Record::Out(0), Record::Out(2)]);
+        assert_eq!(log.last(), Some(&Record::Halt));
+        assert_eq!(recorder.borrow().outputs().len(), 12);
+    }
+
+    #[test]
+    fn test_bus_debugger() {
+        // jump straight to the final move, so only the score triple is drawn
+        let comp = IntcodeComp::new(0, assemble(PAD).unwrap());
+        let script = std::io::Cursor::new(b"ptr 22\nc\n".to_vec());
+        let screen = Rc::new(RefCell::new(Screen::new()));
+        let mut bus = Bus::new(comp).with_debugger(Debugger::with_io(10, Box::new(script), Box::new(std::io::sink())));
+        bus.attach(screen.clone());
+        assert_eq!(bus.run(), Ok(()));
+        assert_eq!(screen.borrow().pixels.len(), 1);
+        assert_eq!(screen.borrow().get(-1, 0), Some(1));
+    }
+
+    #[test]
+    fn test_camera_and_no_input() {
+        let prog = assemble("in -> [x]\nout [x]\nin -> [x]\nhalt\nx: data 0").unwrap();
+        let mut bus = Bus::new(IntcodeComp::new(0, prog.clone()));
+        let mut frames = 0;
+        bus.attach(Camera::new(move || { frames += 1; frames * 10 }));
+        let recorder = Rc::new(RefCell::new(Recorder::new()));
+        bus.attach(recorder.clone());
+        assert_eq!(bus.run(), Ok(()));
+        assert_eq!(recorder.borrow().log, vec![Record::In(10), Record::Out(10), Record::In(20), Record::Halt]);
+
+        let mut bus = Bus::new(IntcodeComp::new(0, prog));
+        bus.attach(Recorder::new());
+        assert!(matches!(bus.run(), Err(IntcodeError::NoInput(_))));
+    }
+}
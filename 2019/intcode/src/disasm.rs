@@ -0,0 +1,218 @@
+//! Disassembler producing mode-aware listings of Intcode programs.
+//!
+//! Code is found by following control flow from address 0, so words that
+//! are never reached (tile maps, lookup tables, scratch cells) are listed as
+//! `DATA` instead of being decoded into nonsense instructions.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::op::{Instr, Mode, Op};
+use crate::Numeric;
+
+/// Number of data words shown per listing line.
+const DATA_PER_LINE: usize = 8;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Line {
+    Code(Instr),
+    Data{addr: usize, values: Vec<Numeric>},
+}
+
+impl Line {
+    pub fn addr(&self) -> usize {
+        match self {
+            Line::Code(i) => i.addr,
+            Line::Data{addr, ..} => *addr,
+        }
+    }
+}
+
+/// Find the addresses reachable as code, starting at address 0.
+///
+/// Besides static jump targets this follows the usual call convention of
+/// the puzzle programs: a return address written to the stack by an
+/// instruction with two immediate operands, right before an unconditional
+/// jump, is treated as code too. Words right after a computed jump are only
+/// taken as code if they decode cleanly up to the next known code or
+/// unconditional instruction.
+pub fn find_code(program: &[Numeric]) -> BTreeMap<usize, Instr> {
+    let mut code = BTreeMap::new();
+    let mut covered = HashSet::new();
+    let mut todo = vec![0];
+    let mut guessed = HashSet::new(); // addresses after computed jumps already tried
+    loop {
+        trace_code(program, &mut code, &mut covered, &mut todo);
+        for instr in code.values() {
+            let next = instr.addr + instr.size();
+            if instr.op.is_jump() && instr.is_unconditional() && instr.static_target().is_none()
+                    && !covered.contains(&next) && plausible_code(program, &covered, next)
+                    && guessed.insert(next) {
+                todo.push(next);
+            }
+        }
+        if todo.is_empty() {
+            return code;
+        }
+    }
+}
+
+/// Follow control flow from every address in `todo`.
+fn trace_code(program: &[Numeric], code: &mut BTreeMap<usize, Instr>,
+              covered: &mut HashSet<usize>, todo: &mut Vec<usize>) {
+    while let Some(addr) = todo.pop() {
+        if covered.contains(&addr) {
+            continue;
+        }
+        let instr = match Instr::decode(program, addr) {
+            Some(i) => i,
+            None => continue,
+        };
+        if (addr..addr+instr.size()).any(|a| covered.contains(&a)) {
+            continue; // overlaps an instruction already decoded
+        }
+        covered.extend(addr..addr+instr.size());
+        if let Some(t) = instr.static_target() {
+            todo.push(t);
+        }
+        if !instr.is_unconditional() {
+            todo.push(addr + instr.size());
+        } else if let Some(ret) = return_address(program, code, &instr) {
+            todo.push(ret);
+        }
+        code.insert(addr, instr);
+    }
+}
+
+/// Whether straight-line decoding from `addr` runs cleanly into known code
+/// or an unconditional instruction.
+fn plausible_code(program: &[Numeric], covered: &HashSet<usize>, mut addr: usize) -> bool {
+    loop {
+        if covered.contains(&addr) {
+            return true;
+        }
+        match Instr::decode(program, addr) {
+            Some(i) if i.is_unconditional() => return true,
+            Some(i) => addr += i.size(),
+            None => return false,
+        }
+    }
+}
+
+/// The return address pushed just before an unconditional jump, if any.
+pub(crate) fn return_address(program: &[Numeric], code: &BTreeMap<usize, Instr>, jump: &Instr) -> Option<usize> {
+    if !jump.op.is_jump() {
+        return None;
+    }
+    let (_, prev) = code.range(..jump.addr).next_back()?;
+    if prev.addr + prev.size() != jump.addr || !matches!(prev.op, Op::Add | Op::Mul) {
+        return None;
+    }
+    let (a, b) = (prev.params[0], prev.params[1]);
+    if a.mode != Mode::Immediate || b.mode != Mode::Immediate {
+        return None;
+    }
+    let val = match prev.op {
+        Op::Add => a.value.checked_add(b.value)?,
+        _ => a.value.checked_mul(b.value)?,
+    };
+    match val >= 0 && (val as usize) < program.len() {
+        true => Some(val as usize),
+        false => None,
+    }
+}
+
+/// Split a program into code and data lines.
+pub fn disassemble(program: &[Numeric]) -> Vec<Line> {
+    let code = find_code(program);
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < program.len() {
+        if let Some(instr) = code.get(&addr) {
+            addr += instr.size();
+            lines.push(Line::Code(instr.clone()));
+            continue;
+        }
+        let start = addr;
+        while addr < program.len() && addr - start < DATA_PER_LINE && !code.contains_key(&addr) {
+            addr += 1;
+        }
+        lines.push(Line::Data{addr: start, values: program[start..addr].to_vec()});
+    }
+    lines
+}
+
+/// Render a single line as `0042: ADD [rb+3], #5 -> [100]`.
+pub fn format_line(line: &Line) -> String {
+    match line {
+        Line::Code(instr) => format!("{:04}: {}", instr.addr, instr),
+        Line::Data{addr, values} => {
+            let vals: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            format!("{:04}: DATA {}", addr, vals.join(", "))
+        },
+    }
+}
+
+/// Full listing of a program, one line per instruction or data run.
+pub fn listing(program: &[Numeric]) -> String {
+    let mut out = String::new();
+    for line in disassemble(program).iter() {
+        out.push_str(&format_line(line));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::str_to_vec;
+
+    #[test]
+    fn test_listing() {
+        let prog = str_to_vec("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!(listing(&prog), "\
+0000: IN -> [9]
+0002: EQ [9], [10] -> [9]
+0006: OUT [9]
+0008: HALT
+0009: DATA -1, 8
+");
+    }
+
+    #[test]
+    fn test_jump_targets() {
+        // the jump skips over a data word that is not a valid instruction
+        let prog = str_to_vec("1105,1,4,12345,104,7,99");
+        let lines = disassemble(&prog);
+        assert_eq!(lines[1], Line::Data{addr: 3, values: vec![12345]});
+        assert_eq!(format_line(&lines[2]), "0004: OUT #7");
+    }
+
+    #[test]
+    fn test_call_return() {
+        // push return address 9 to [rb+0], call 10, which returns via [rb+0]
+        let prog = str_to_vec("109,20,21101,0,9,0,1105,1,10,99,2106,0,0");
+        let code = find_code(&prog);
+        assert_eq!(code.keys().copied().collect::<Vec<_>>(), vec![0, 2, 6, 9, 10]);
+        assert_eq!(code[&10].to_string(), "JF #0, [rb+0]");
+    }
+
+    #[test]
+    fn test_guess_after_computed_jump_terminates() {
+        // the words after the computed jump decode, but run into the
+        // return address 8, so they are never taken as code
+        let prog = str_to_vec("1101,0,8,30,106,0,30,1101,99,0,0,99");
+        let code = find_code(&prog);
+        assert_eq!(code.keys().copied().collect::<Vec<_>>(), vec![0, 4, 8]);
+        assert_eq!(format_line(&disassemble(&prog)[2]), "0007: DATA 1101");
+    }
+
+    #[test]
+    fn test_quine_is_decoded() {
+        let prog = str_to_vec("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+        let lines = disassemble(&prog);
+        assert_eq!(format_line(&lines[0]), "0000: ARB #1");
+        assert_eq!(format_line(&lines[1]), "0002: OUT [rb-1]");
+        assert_eq!(format_line(&lines[5]), "0015: HALT");
+    }
+}
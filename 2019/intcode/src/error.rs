@@ -0,0 +1,62 @@
+use std::fmt;
+
+use crate::memory::MemoryError;
+use crate::Numeric;
+
+/// Where a machine was when it failed.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Location {
+    pub name: usize, // name of the machine
+    pub ptr: usize, // instruction pointer
+    pub instr: Numeric, // raw instruction word at `ptr`
+}
+
+/// A fault in the program being run, rather than in the VM.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum IntcodeError {
+    UnknownOp(Location), // opcode is not one of 1-9 or 99
+    BadMode(Location, Numeric), // parameter mode is not 0, 1 or 2
+    ImmediateWrite(Location), // write target in immediate mode
+    Memory(Location, MemoryError), // negative or out-of-bounds address
+    EndOfMemory(Location), // ran off the end of the program
+    NoInput(Location), // input needed but none will come
+    ChannelClosed(Location), // the other end of an IO channel went away
+    Overflow(Location), // a result does not fit in the word type
+    BadOutput(Location, Numeric), // output the caller cannot make sense of
+}
+
+impl IntcodeError {
+    pub fn location(&self) -> Location {
+        match *self {
+            IntcodeError::UnknownOp(at) => at,
+            IntcodeError::BadMode(at, _) => at,
+            IntcodeError::ImmediateWrite(at) => at,
+            IntcodeError::Memory(at, _) => at,
+            IntcodeError::EndOfMemory(at) => at,
+            IntcodeError::NoInput(at) => at,
+            IntcodeError::ChannelClosed(at) => at,
+            IntcodeError::Overflow(at) => at,
+            IntcodeError::BadOutput(at, _) => at,
+        }
+    }
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let at = self.location();
+        write!(f, "machine {} at {} (instr {}): ", at.name, at.ptr, at.instr)?;
+        match self {
+            IntcodeError::UnknownOp(_) => write!(f, "unknown opcode {}", at.instr % 100),
+            IntcodeError::BadMode(_, mode) => write!(f, "bad parameter mode {}", mode),
+            IntcodeError::ImmediateWrite(_) => write!(f, "write in immediate mode"),
+            IntcodeError::Memory(_, e) => write!(f, "{}", e),
+            IntcodeError::EndOfMemory(_) => write!(f, "ran off the end of memory"),
+            IntcodeError::NoInput(_) => write!(f, "no more input available"),
+            IntcodeError::ChannelClosed(_) => write!(f, "channel closed"),
+            IntcodeError::Overflow(_) => write!(f, "arithmetic overflow"),
+            IntcodeError::BadOutput(_, val) => write!(f, "unexpected output {}", val),
+        }
+    }
+}
+
+impl std::error::Error for IntcodeError {}
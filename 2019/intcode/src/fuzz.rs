@@ -0,0 +1,691 @@
+//! Differential fuzzing of Intcode implementations.
+//!
+//! Random programs are built from a small tree of items: arithmetic and
+//! comparisons on a block of data cells, input, output, forward jumps, and
+//! loops closed by a counter that nothing else writes. Loops only ever run
+//! their fixed count, so the programs terminate, even on an interpreter
+//! without a step limit, unless they rewrite themselves.
+//!
+//! With `Config::code_writes`, some writes land in the program's own code,
+//! and with `Config::loose_words` some instruction words carry mode digits
+//! the VM ignores or rejects. Such programs can fault or spin until the step
+//! limit; that is compared like any other outcome.
+//!
+//! Each program runs on every `Implementation`, and the first one whose
+//! output, final memory or halting state differs from the first
+//! implementation's is reported. The `regressions` are run before any random
+//! programs.
+//!
+//! A program whose values leave `Config::max_abs` on the reference run is
+//! skipped rather than compared, so word size never counts as a divergence:
+//! i64 against i128, or against an old i32 interpreter.
+//!
+//! Failing programs are minimised by dropping items, unrolling loops and
+//! zeroing data for as long as the divergence remains.
+
+use std::fmt;
+
+use crate::comp::{Event, IntcodeComp};
+use crate::disasm::listing;
+use crate::memory::{Memory, DEFAULT_LIMIT};
+use crate::op::Op;
+use crate::threaded::ThreadedComp;
+use crate::word::Word;
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Config {
+    pub items: usize, // most items in a block
+    pub depth: usize, // deepest loop nesting
+    pub data: usize, // number of data cells
+    pub relative: bool, // address data through the relative base
+    pub max_abs: Numeric, // skip programs whose values get larger than this
+    pub max_steps: u64, // give up on a run after this many instructions
+    pub code_writes: bool, // let some writes land in the program's code
+    pub loose_words: bool, // give some instruction words stray or invalid mode digits
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config{items: 12, depth: 2, data: 8, relative: true, max_abs: i32::MAX as Numeric, max_steps: 100_000,
+               code_writes: true, loose_words: true}
+    }
+}
+
+/// xorshift64*, so a seed always gives the same programs.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn range(&mut self, lo: Numeric, hi: Numeric) -> Numeric {
+        lo + (self.next() % (hi - lo + 1) as u64) as Numeric
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Operand {
+    Imm(Numeric),
+    Cell(usize, bool), // data cell, addressed relative to rb if true
+    Code(usize), // word of the program before the data, by index modulo its length
+}
+
+/// Words worth writing into code: opcodes with and without modes, and
+/// values that are not instructions at all.
+const CODE_WORDS: [Numeric; 14] = [0, 1, 2, 4, 5, 99, 104, 109, 1001, 1003, 1105, 1106, 20101, -1];
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Item {
+    Arith(Op, Operand, Operand, Operand), // add, mul, lt or eq into a cell
+    In(Operand),
+    Out(Operand),
+    Jump(Op, Operand, usize), // jt or jf over this many following items
+    Loop(Numeric, Vec<Item>), // run the body this many times
+    Loose(Numeric, Box<Item>), // the item with this added to its first word
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Arith(..) => 4,
+            Item::In(_) | Item::Out(_) => 2,
+            Item::Jump(..) => 3,
+            Item::Loop(_, body) => 4 + size(body) + 7,
+            Item::Loose(_, item) => item.size(),
+        }
+    }
+}
+
+fn size(block: &[Item]) -> usize {
+    block.iter().map(|i| i.size()).sum()
+}
+
+fn loops(block: &[Item]) -> usize {
+    block.iter().map(|i| match i {
+        Item::Loop(_, body) => 1 + loops(body),
+        Item::Loose(_, item) => loops(std::slice::from_ref(&**item)),
+        _ => 0,
+    }).sum()
+}
+
+/// A generated program: the item tree, initial data and enough input.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Case {
+    pub items: Vec<Item>,
+    pub data: Vec<Numeric>,
+    pub relative: bool,
+    pub input: Vec<Numeric>,
+}
+
+impl Case {
+    pub fn generate(seed: u64, config: &Config) -> Case {
+        let mut rng = Rng::new(seed);
+        let items = gen_block(&mut rng, config, config.depth);
+        let data = (0..config.data).map(|_| rng.range(-9, 9)).collect();
+        let mut case = Case{items, data, relative: config.relative, input: Vec::new()};
+        case.input = (0..case.inputs_needed(&case.items, 1)).map(|_| rng.range(-9, 9)).collect();
+        case
+    }
+
+    fn inputs_needed(&self, block: &[Item], times: usize) -> usize {
+        block.iter().map(|i| match i {
+            Item::In(_) => times,
+            Item::Loop(n, body) => self.inputs_needed(body, times * *n as usize),
+            Item::Loose(_, item) => self.inputs_needed(std::slice::from_ref(&**item), times),
+            _ => 0,
+        }).sum()
+    }
+
+    /// Lay the program out as code, `HALT`, data cells, loop counters.
+    pub fn program(&self) -> Vec<Numeric> {
+        let start = if self.relative { 2 } else { 0 };
+        let data_base = start + size(&self.items) + 1;
+        let mut out = Vec::new();
+        if self.relative {
+            out.extend_from_slice(&[109, data_base as Numeric]);
+        }
+        let mut counter = data_base + self.data.len();
+        emit(&self.items, data_base, &mut counter, &mut out);
+        out.push(99);
+        out.extend_from_slice(&self.data);
+        out.extend(std::iter::repeat_n(0, loops(&self.items)));
+        out
+    }
+}
+
+fn gen_operand(rng: &mut Rng, config: &Config, imm: bool) -> Operand {
+    if imm && rng.below(3) == 0 {
+        return Operand::Imm(rng.range(-9, 9));
+    }
+    Operand::Cell(rng.below(config.data), config.relative && rng.below(2) == 0)
+}
+
+/// Somewhere in the code, if writes may go there this time.
+fn gen_code(rng: &mut Rng, config: &Config) -> Option<Operand> {
+    match config.code_writes && rng.below(5) == 0 {
+        true => Some(Operand::Code(rng.below(64))),
+        false => None,
+    }
+}
+
+/// Digits to add to an instruction word with `params` parameters: a mode
+/// for a parameter it does not have, a digit past the modes, or a mode
+/// that does not exist.
+fn gen_extra(rng: &mut Rng, params: usize) -> Numeric {
+    let digit = |p: usize| (10 as Numeric).pow(2 + p as u32);
+    match rng.below(3) {
+        0 if params < 3 => digit(params + rng.below(3 - params)) * rng.range(1, 2),
+        1 => 100_000 * rng.range(1, 9),
+        _ => digit(rng.below(params)) * 3,
+    }
+}
+
+fn gen_block(rng: &mut Rng, config: &Config, depth: usize) -> Vec<Item> {
+    let n = 1 + rng.below(config.items);
+    let mut block = Vec::new();
+    for i in 0..n {
+        let (item, params) = match rng.below(10) {
+            0..=3 => match gen_code(rng, config) {
+                Some(code) => (Item::Arith(Op::Add, Operand::Imm(CODE_WORDS[rng.below(CODE_WORDS.len())]),
+                                           Operand::Imm(0), code), 3),
+                None => {
+                    let op = [Op::Add, Op::Mul, Op::Lt, Op::Eq][rng.below(4)];
+                    (Item::Arith(op, gen_operand(rng, config, true), gen_operand(rng, config, true),
+                                 gen_operand(rng, config, false)), 3)
+                },
+            },
+            4 => {
+                let dst = gen_code(rng, config).unwrap_or_else(|| gen_operand(rng, config, false));
+                (Item::In(dst), 1)
+            },
+            5 | 6 => (Item::Out(gen_operand(rng, config, true)), 1),
+            7 => {
+                let op = [Op::Jt, Op::Jf][rng.below(2)];
+                (Item::Jump(op, gen_operand(rng, config, true), rng.below(n - i)), 2)
+            },
+            _ if depth > 0 => (Item::Loop(rng.range(1, 4), gen_block(rng, config, depth - 1)), 0),
+            _ => (Item::Out(gen_operand(rng, config, true)), 1),
+        };
+        let item = match config.loose_words && params > 0 && rng.below(8) == 0 {
+            true => Item::Loose(gen_extra(rng, params), Box::new(item)),
+            false => item,
+        };
+        block.push(item);
+    }
+    block
+}
+
+/// Encode an instruction with its operands' modes.
+fn instr(op: Op, args: &[Operand], data_base: usize, out: &mut Vec<Numeric>) {
+    let mut word = op.code();
+    let mut scale = 100;
+    let mut vals = Vec::new();
+    for a in args {
+        let (mode, val) = match *a {
+            Operand::Imm(v) => (1, v),
+            Operand::Cell(i, true) => (2, i as Numeric),
+            Operand::Cell(i, false) => (0, (data_base + i) as Numeric),
+            Operand::Code(i) => (0, (i % data_base) as Numeric),
+        };
+        word += mode * scale;
+        scale *= 10;
+        vals.push(val);
+    }
+    out.push(word);
+    out.extend(vals);
+}
+
+fn emit(block: &[Item], data_base: usize, counter: &mut usize, out: &mut Vec<Numeric>) {
+    // where each item starts, plus the end of the block
+    let mut addrs = vec![out.len()];
+    for item in block {
+        let last = *addrs.last().unwrap();
+        addrs.push(last + item.size());
+    }
+    for (i, item) in block.iter().enumerate() {
+        let start = out.len();
+        let (item, extra) = match item {
+            Item::Loose(extra, item) => (&**item, *extra),
+            item => (item, 0),
+        };
+        match item {
+            Item::Arith(op, a, b, c) => instr(*op, &[*a, *b, *c], data_base, out),
+            Item::In(a) => instr(Op::In, &[*a], data_base, out),
+            Item::Out(a) => instr(Op::Out, &[*a], data_base, out),
+            Item::Jump(op, cond, skip) => {
+                let to = addrs[(i + 1 + skip).min(block.len())] as Numeric;
+                instr(*op, &[*cond, Operand::Imm(to)], data_base, out);
+            },
+            Item::Loop(n, body) => {
+                let ctr = *counter as Numeric;
+                *counter += 1;
+                out.extend_from_slice(&[1101, 0, *n, ctr]);
+                let top = out.len() as Numeric;
+                emit(body, data_base, counter, out);
+                out.extend_from_slice(&[1001, ctr, -1, ctr, 1005, ctr, top]);
+            },
+            Item::Loose(..) => unreachable!("loose items are not nested"),
+        }
+        out[start] += extra;
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum End {
+    Halted,
+    Fault(String), // the error, as displayed
+    StepLimit,
+}
+
+/// Everything observable about a run.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Outcome {
+    pub output: Vec<Numeric>,
+    pub memory: Vec<Numeric>, // final memory without trailing zeros
+    pub end: End,
+}
+
+impl Outcome {
+    pub fn new(output: Vec<Numeric>, mut memory: Vec<Numeric>, end: End) -> Outcome {
+        while memory.last() == Some(&0) {
+            memory.pop();
+        }
+        Outcome{output, memory, end}
+    }
+
+    /// The first way `other` differs from this outcome.
+    pub fn difference(&self, other: &Outcome) -> Option<String> {
+        if self.output != other.output {
+            return Some(format!("output {:?} vs {:?}", self.output, other.output));
+        }
+        if self.memory != other.memory {
+            let (a, b) = (&self.memory, &other.memory);
+            let at = (0..a.len().max(b.len())).find(|i| a.get(*i) != b.get(*i)).unwrap();
+            return Some(format!("memory [{}] = {:?} vs {:?}", at, a.get(at), b.get(at)));
+        }
+        if self.end != other.end {
+            return Some(format!("end {:?} vs {:?}", self.end, other.end));
+        }
+        None
+    }
+}
+
+/// Something that runs Intcode programs.
+pub trait Implementation {
+    fn name(&self) -> String;
+    fn run(&self, program: &[Numeric], input: &[Numeric], max_steps: u64) -> Outcome;
+}
+
+fn run_comp<W: Word>(mut comp: IntcodeComp<W>, input: &[Numeric], max_steps: u64) -> Outcome {
+    comp.input.extend(input.iter().map(|v| W::from_i64(*v)));
+    let mut output = Vec::new();
+    let mut end = End::StepLimit;
+    for _ in 0..max_steps {
+        match comp.step() {
+            Ok(Some(Event::Output(v))) => output.push(v),
+            Ok(Some(Event::Halted)) => {
+                end = End::Halted;
+                break;
+            },
+            Ok(Some(Event::NeedsInput)) => {
+                end = End::Fault(crate::error::IntcodeError::NoInput(comp.location()).to_string());
+                break;
+            },
+            Ok(None) => (),
+            Err(e) => {
+                end = End::Fault(e.to_string());
+                break;
+            },
+        }
+    }
+    // wide words that no longer fit are kept out of the comparison
+    let narrow = |v: &W| v.to_i64().unwrap_or(Numeric::MAX);
+    Outcome::new(output.iter().map(narrow).collect(), comp.instrs.to_vec().iter().map(narrow).collect(), end)
+}
+
+/// `IntcodeComp` on dense memory.
+pub struct Interpreter;
+
+impl Implementation for Interpreter {
+    fn name(&self) -> String {
+        "interpreter".to_string()
+    }
+
+    fn run(&self, program: &[Numeric], input: &[Numeric], max_steps: u64) -> Outcome {
+        run_comp(IntcodeComp::new(0, program.to_vec()), input, max_steps)
+    }
+}
+
+/// `IntcodeComp` on paged memory.
+pub struct Paged;
+
+impl Implementation for Paged {
+    fn name(&self) -> String {
+        "paged".to_string()
+    }
+
+    fn run(&self, program: &[Numeric], input: &[Numeric], max_steps: u64) -> Outcome {
+        // the dense limit, so a far address faults the same way on both
+        run_comp(IntcodeComp::with_memory(0, Memory::paged(program).with_limit(DEFAULT_LIMIT)), input, max_steps)
+    }
+}
+
+/// `IntcodeComp` with any word type, e.g. `Words::<i128>::new()`.
+pub struct Words<W: Word>(std::marker::PhantomData<W>);
+
+impl<W: Word> Words<W> {
+    pub fn new() -> Words<W> {
+        Words(std::marker::PhantomData)
+    }
+}
+
+impl<W: Word> Default for Words<W> {
+    fn default() -> Words<W> {
+        Words::new()
+    }
+}
+
+impl<W: Word> Implementation for Words<W> {
+    fn name(&self) -> String {
+        format!("words<{}>", std::any::type_name::<W>())
+    }
+
+    fn run(&self, program: &[Numeric], input: &[Numeric], max_steps: u64) -> Outcome {
+        let words = program.iter().map(|v| W::from_i64(*v)).collect();
+        run_comp(IntcodeComp::<W>::from_words(0, words), input, max_steps)
+    }
+}
+
+/// The pre-decoded `ThreadedComp`.
+pub struct Threaded;
+
+impl Implementation for Threaded {
+    fn name(&self) -> String {
+        "threaded".to_string()
+    }
+
+    fn run(&self, program: &[Numeric], input: &[Numeric], max_steps: u64) -> Outcome {
+        let mut comp = ThreadedComp::from_program(0, program.to_vec());
+        for v in input {
+            comp.push_input(*v);
+        }
+        let mut output = Vec::new();
+        let mut end = End::StepLimit;
+        for _ in 0..max_steps {
+            match comp.step() {
+                Ok(Some(Event::Output(v))) => output.push(v),
+                Ok(Some(Event::Halted)) => {
+                    end = End::Halted;
+                    break;
+                },
+                Ok(Some(Event::NeedsInput)) => {
+                    end = End::Fault(crate::error::IntcodeError::NoInput(comp.comp().location()).to_string());
+                    break;
+                },
+                Ok(None) => (),
+                Err(e) => {
+                    end = End::Fault(e.to_string());
+                    break;
+                },
+            }
+        }
+        Outcome::new(output, comp.comp().instrs.to_vec(), end)
+    }
+}
+
+/// Every implementation in this crate.
+pub fn implementations() -> Vec<Box<dyn Implementation>> {
+    #[cfg_attr(not(feature = "num-bigint"), allow(unused_mut))]
+    let mut impls: Vec<Box<dyn Implementation>> = vec![
+        Box::new(Interpreter), Box::new(Paged), Box::new(Threaded), Box::new(Words::<i128>::new()),
+    ];
+    #[cfg(feature = "num-bigint")]
+    impls.push(Box::new(Words::<num_bigint::BigInt>::new()));
+    impls
+}
+
+/// Whether every value the program computes stays within `max_abs`.
+fn in_range(case: &Case, config: &Config) -> bool {
+    let program = case.program();
+    let ok = |v: Numeric| v.checked_abs().is_some_and(|a| a <= config.max_abs);
+    if !program.iter().copied().all(ok) {
+        return false;
+    }
+    let mut comp = IntcodeComp::new(0, program);
+    comp.input.extend(case.input.iter().copied());
+    for _ in 0..config.max_steps {
+        // only the cell an instruction writes can leave the range
+        let target = comp.write_target();
+        match comp.step() {
+            Ok(Some(Event::Output(v))) if !ok(v) => return false,
+            Ok(Some(Event::Halted)) | Ok(Some(Event::NeedsInput)) | Err(_) => break,
+            _ => (),
+        }
+        let written = target.filter(|a| *a >= 0).and_then(|a| comp.instrs.get(a as usize));
+        if written.is_some_and(|v| !ok(v)) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Cases that once made implementations disagree.
+pub fn regressions() -> Vec<Case> {
+    vec![
+        // an `IN` with a stray mode digit rewrites the `OUT #1` at the top of
+        // the loop, which `ThreadedComp` once kept running as decoded
+        Case{
+            items: vec![Item::Loop(2, vec![
+                Item::Out(Operand::Imm(1)),
+                Item::Loose(1000, Box::new(Item::In(Operand::Code(5)))),
+            ])],
+            data: Vec::new(),
+            relative: false,
+            input: vec![5, 6],
+        },
+    ]
+}
+
+/// A program on which two implementations disagree.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Divergence {
+    pub seed: Option<u64>, // None for one of the `regressions`
+    pub case: Case,
+    pub reference: String, // name of the first implementation
+    pub other: String, // name of the one that disagreed
+    pub difference: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.seed {
+            Some(seed) => write!(f, "seed {}", seed)?,
+            None => write!(f, "regression")?,
+        }
+        writeln!(f, ": {} and {} differ: {}", self.reference, self.other, self.difference)?;
+        writeln!(f, "input {:?}", self.case.input)?;
+        let words: Vec<String> = self.case.program().iter().map(|w| w.to_string()).collect();
+        writeln!(f, "program {}", words.join(","))?;
+        write!(f, "{}", listing(&self.case.program()))
+    }
+}
+
+/// The first implementation that disagrees with `impls[0]` on `case`.
+fn diverges(case: &Case, config: &Config, impls: &[Box<dyn Implementation>]) -> Option<(String, String)> {
+    let program = case.program();
+    let reference = impls[0].run(&program, &case.input, config.max_steps);
+    impls[1..].iter().find_map(|imp| {
+        let outcome = imp.run(&program, &case.input, config.max_steps);
+        reference.difference(&outcome).map(|d| (imp.name(), d))
+    })
+}
+
+/// Smaller versions of a block: each item dropped, each loop unrolled to
+/// its body or run once, and the same inside every loop body.
+fn shrink(block: &[Item]) -> Vec<Vec<Item>> {
+    let mut out = Vec::new();
+    for i in 0..block.len() {
+        let mut smaller = block.to_vec();
+        smaller.remove(i);
+        out.push(smaller);
+        if let Item::Loose(_, item) = &block[i] {
+            let mut tight = block.to_vec();
+            tight[i] = (**item).clone();
+            out.push(tight);
+        }
+        if let Item::Loop(n, body) = &block[i] {
+            let mut unrolled = block[..i].to_vec();
+            unrolled.extend(body.iter().cloned());
+            unrolled.extend(block[i+1..].iter().cloned());
+            out.push(unrolled);
+            if *n > 1 {
+                let mut once = block.to_vec();
+                once[i] = Item::Loop(1, body.clone());
+                out.push(once);
+            }
+            for b in shrink(body) {
+                let mut inner = block.to_vec();
+                inner[i] = Item::Loop(*n, b);
+                out.push(inner);
+            }
+        }
+    }
+    out
+}
+
+/// Shrink `case` for as long as it still makes some implementation
+/// disagree with `impls[0]`.
+pub fn minimise(case: &Case, config: &Config, impls: &[Box<dyn Implementation>]) -> Case {
+    let mut best = case.clone();
+    'outer: loop {
+        let mut candidates: Vec<Case> = shrink(&best.items).into_iter()
+            .map(|items| Case{items, ..best.clone()})
+            .collect();
+        for i in 0..best.data.len() {
+            if best.data[i] != 0 {
+                let mut c = best.clone();
+                c.data[i] = 0;
+                candidates.push(c);
+            }
+        }
+        for c in candidates {
+            if in_range(&c, config) && diverges(&c, config, impls).is_some() {
+                best = c;
+                continue 'outer;
+            }
+        }
+        let needed = best.inputs_needed(&best.items, 1);
+        best.input.truncate(needed);
+        return best;
+    }
+}
+
+/// Run the `regressions` and then `cases` random programs from `seed` on
+/// every implementation, returning how many were compared, or the first
+/// divergence, minimised.
+pub fn fuzz(config: &Config, impls: &[Box<dyn Implementation>], seed: u64, cases: usize) -> Result<usize, Box<Divergence>> {
+    let known = regressions().into_iter().map(|c| (None, c));
+    let random = (seed..seed + cases as u64).map(|s| (Some(s), Case::generate(s, config)));
+    let mut compared = 0;
+    for (seed, case) in known.chain(random) {
+        if !in_range(&case, config) {
+            continue;
+        }
+        compared += 1;
+        if diverges(&case, config, impls).is_some() {
+            let case = minimise(&case, config, impls);
+            let (other, difference) = diverges(&case, config, impls).unwrap();
+            return Err(Box::new(Divergence{seed, case, reference: impls[0].name(), other, difference}));
+        }
+    }
+    Ok(compared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agree() {
+        let config = Config::default();
+        let compared = fuzz(&config, &implementations(), 1, 200).unwrap();
+        assert!(compared > 100);
+    }
+
+    #[test]
+    fn test_regressions() {
+        let case = &regressions()[0];
+        assert_eq!(case.program(), vec![1101,0,2,16,104,1,1003,5,1001,16,-1,16,1005,16,4,99,0]);
+        let config = Config::default();
+        for case in regressions() {
+            assert_eq!(diverges(&case, &config, &implementations()), None);
+        }
+    }
+
+    #[test]
+    fn test_generated_programs_halt() {
+        let config = Config{relative: false, code_writes: false, loose_words: false, ..Config::default()};
+        for seed in 0..50 {
+            let case = Case::generate(seed, &config);
+            let outcome = Interpreter.run(&case.program(), &case.input, config.max_steps);
+            assert_eq!(outcome.end, End::Halted, "seed {}", seed);
+        }
+    }
+
+    /// Runs `eq` as `lt`.
+    struct NoEquals;
+
+    impl Implementation for NoEquals {
+        fn name(&self) -> String {
+            "no-equals".to_string()
+        }
+
+        fn run(&self, program: &[Numeric], input: &[Numeric], max_steps: u64) -> Outcome {
+            let mut comp = IntcodeComp::new(0, program.to_vec());
+            comp.input.extend(input.iter().copied());
+            let mut output = Vec::new();
+            for _ in 0..max_steps {
+                let ptr = comp.ptr;
+                let word = comp.instrs[ptr];
+                let eq = comp.instrs.decode(ptr).is_some_and(|i| i.op == Op::Eq);
+                if eq {
+                    comp.set_mem(ptr, word - 1).unwrap();
+                }
+                let event = comp.step().unwrap();
+                if eq && comp.instrs[ptr] == word - 1 {
+                    comp.set_mem(ptr, word).unwrap();
+                }
+                match event {
+                    Some(Event::Output(v)) => output.push(v),
+                    Some(Event::Halted) => break,
+                    _ => (),
+                }
+            }
+            Outcome::new(output, comp.instrs.to_vec(), End::Halted)
+        }
+    }
+
+    #[test]
+    fn test_divergence_minimised() {
+        let config = Config{relative: false, code_writes: false, loose_words: false, ..Config::default()};
+        let impls: Vec<Box<dyn Implementation>> = vec![Box::new(Interpreter), Box::new(NoEquals)];
+        let d = fuzz(&config, &impls, 1, 1000).unwrap_err();
+        assert_eq!(d.other, "no-equals");
+        assert!(size(&d.case.items) <= 6, "{}", d);
+        assert!(d.to_string().contains("EQ"), "{}", d);
+        assert_eq!(d.case.input, vec![]);
+    }
+}
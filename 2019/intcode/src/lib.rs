@@ -0,0 +1,70 @@
+//! Shared Intcode computer for the 2019 puzzles.
+//!
+//! Every Intcode day builds on the `IntcodeComp` interpreter here, so a fix
+//! to the VM reaches every puzzle at once. The VM is driven synchronously
+//! with `step`/`run_until_event`; the `tokio` feature adds the channel-based
+//! `ChannelComp` adapter on top. Machines compute in `i64` words by default;
+//! `word` has the wider alternatives.
+
+pub mod ascii;
+pub mod asm;
+pub mod cfg;
+#[cfg(feature = "tokio")]
+mod channel;
+mod comp;
+pub mod debugger;
+pub mod device;
+pub mod disasm;
+mod error;
+pub mod fuzz;
+pub mod memory;
+pub mod network;
+pub mod op;
+pub mod phase;
+mod profile;
+mod snapshot;
+mod threaded;
+pub mod trace;
+pub mod word;
+
+pub use ascii::{AsciiComp, AsciiOutput};
+#[cfg(feature = "tokio")]
+pub use channel::{ChannelComp, IO};
+pub use comp::{Event, IntcodeComp};
+pub use debugger::Debugger;
+pub use error::{IntcodeError, Location};
+pub use memory::{Memory, MemoryError, MemoryKind};
+pub use op::{Instr, Mode, Op, Param};
+pub use profile::Profiler;
+pub use snapshot::Snapshot;
+pub use threaded::ThreadedComp;
+pub use trace::{TraceLevel, Tracer};
+pub use word::Word;
+
+pub type Numeric = i64;
+
+/// Parse a comma-separated Intcode program.
+pub fn str_to_vec(input: &str) -> Vec<Numeric> {
+    let mut instrs = Vec::new();
+    for e in input.trim().split(',') {
+        instrs.push(e.trim().parse::<Numeric>().unwrap());
+    }
+    instrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_str_to_vec() {
+        let input = "1,2,3,4";
+        assert_eq!(str_to_vec(input), vec![1,2,3,4]);
+    }
+
+    #[test]
+    fn test_str_to_vec_whitespace() {
+        let input = "1, -2,3\n";
+        assert_eq!(str_to_vec(input), vec![1,-2,3]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::env;
+use std::fs::read_to_string;
+use std::process::exit;
+
+use intcode::{asm, cfg, disasm, fuzz, str_to_vec, Debugger, Event, IntcodeComp, Numeric, Profiler, Tracer};
+
+fn read_program(filename: &str) -> Vec<Numeric> {
+    let text = read_to_string(filename)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", filename, e));
+    str_to_vec(text.trim().trim_matches('"'))
+}
+
+fn usage() -> ! {
+    eprintln!("usage: intcode run <program> [input,...]");
+    eprintln!("       intcode dis <program>");
+    eprintln!("       intcode asm <source>");
+    eprintln!("       intcode cfg <program>");
+    eprintln!("       intcode debug <program> [input,...]");
+    eprintln!("       intcode profile <program> [input,...]");
+    eprintln!("       intcode fuzz <cases> [seed]");
+    exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        usage();
+    }
+    match args[1].as_str() {
+        "run" => {
+            // the one machine here may write a trace file
+            let tracer = Tracer::from_env(0).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1);
+            });
+            let mut comp = IntcodeComp::new(0, read_program(&args[2])).with_tracer(tracer);
+            if let Some(input) = args.get(3) {
+                comp.input.extend(str_to_vec(input));
+            }
+            let result = comp.run_to_halt();
+            let _ = comp.tracer.flush();
+            match result {
+                Ok(output) => println!("output: {:?}", output),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                },
+            }
+        },
+        "dis" => print!("{}", disasm::listing(&read_program(&args[2]))),
+        "asm" => {
+            let source = read_to_string(&args[2])
+                .unwrap_or_else(|e| panic!("cannot read {}: {}", args[2], e));
+            match asm::assemble(&source) {
+                Ok(prog) => {
+                    let words: Vec<String> = prog.iter().map(|x| x.to_string()).collect();
+                    println!("{}", words.join(","));
+                },
+                Err(e) => {
+                    eprintln!("{}: {}", args[2], e);
+                    exit(1);
+                },
+            }
+        },
+        "cfg" => print!("{}", cfg::Cfg::new(&read_program(&args[2])).to_dot()),
+        "debug" => {
+            let mut comp = IntcodeComp::new(0, read_program(&args[2]));
+            if let Some(input) = args.get(3) {
+                comp.input.extend(str_to_vec(input));
+            }
+            let mut debugger = Debugger::new(100);
+            loop {
+                match debugger.run_until_event(&mut comp) {
+                    Ok(Event::Output(x)) => println!("output: {}", x),
+                    Ok(Event::NeedsInput) => {
+                        eprintln!("out of input at {}", comp.ptr);
+                        exit(1);
+                    },
+                    Ok(Event::Halted) => break,
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(1);
+                    },
+                }
+            }
+        },
+        "profile" => {
+            let program = read_program(&args[2]);
+            let mut comp = IntcodeComp::new(0, program.clone());
+            comp.profiler = Some(Profiler::new()); // reported below, even on an error
+            if let Some(input) = args.get(3) {
+                comp.input.extend(str_to_vec(input));
+            }
+            match comp.run_to_halt() {
+                Ok(output) => println!("output: {:?}", output),
+                Err(e) => eprintln!("{}", e),
+            }
+            let profiler = comp.profiler.as_ref().unwrap();
+            print!("{}", profiler.report(&comp.instrs, 20));
+            print!("{}", profiler.annotate(&program));
+        },
+        "fuzz" => {
+            let cases = args[2].parse().unwrap_or_else(|_| usage());
+            let seed = args.get(3).map_or(1, |s| s.parse().unwrap_or_else(|_| usage()));
+            match fuzz::fuzz(&fuzz::Config::default(), &fuzz::implementations(), seed, cases) {
+                Ok(compared) => println!("{} programs compared, no divergence", compared),
+                Err(d) => {
+                    print!("{}", d);
+                    exit(1);
+                },
+            }
+        },
+        _ => usage(),
+    }
+}
@@ -0,0 +1,273 @@
+//! Intcode memory backends.
+//!
+//! `Dense` keeps memory in a `Vec` that grows up to the highest address
+//! touched, which is the fastest option for the puzzle programs. `Paged`
+//! allocates fixed-size pages on first write, so a program that scribbles on
+//! a far-away address costs one page instead of gigabytes. Both refuse
+//! negative addresses and addresses at or above the configured limit, which
+//! is `DEFAULT_LIMIT` for dense memory and `PAGED_LIMIT` for paged memory.
+//! Reads never change memory: untouched cells read as zero on both.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Index;
+
+use crate::op::Instr;
+use crate::word::Word;
+use crate::Numeric;
+
+/// Words per page of `Paged` memory.
+pub const PAGE_SIZE: usize = 1024;
+
+/// Default limit on addresses for dense memory: 16M words, 128MB.
+pub const DEFAULT_LIMIT: usize = 1 << 24;
+
+/// Default limit on addresses for paged memory: 4G words, of which only
+/// the pages written are allocated.
+pub const PAGED_LIMIT: usize = 1 << 32;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MemoryKind {
+    Dense,
+    Paged,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MemoryError {
+    Negative(Numeric), // address below zero
+    OutOfBounds{addr: Numeric, limit: usize}, // address at or above the limit
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MemoryError::Negative(addr) => write!(f, "negative address {}", addr),
+            MemoryError::OutOfBounds{addr, limit} =>
+                write!(f, "address {} is beyond the memory limit of {}", addr, limit),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Backing<W> {
+    Dense(Vec<W>),
+    Paged(HashMap<usize, Box<[W]>>), // pages of PAGE_SIZE words
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Memory<W: Word = Numeric> {
+    backing: Backing<W>,
+    len: usize, // one past the highest address written
+    pub limit: usize, // addresses must be below this
+    zero: W, // what untouched memory reads as
+}
+
+impl Memory {
+    pub fn new(kind: MemoryKind, program: &[Numeric]) -> Memory {
+        Memory::from_words(kind, program)
+    }
+
+    pub fn dense(program: Vec<Numeric>) -> Memory {
+        Memory{len: program.len(), backing: Backing::Dense(program), limit: DEFAULT_LIMIT, zero: 0}
+    }
+
+    pub fn paged(program: &[Numeric]) -> Memory {
+        Memory::new(MemoryKind::Paged, program)
+    }
+}
+
+impl<W: Word> Memory<W> {
+    /// Memory of any word type holding `program`.
+    pub fn from_words(kind: MemoryKind, program: &[W]) -> Memory<W> {
+        let (backing, limit) = match kind {
+            MemoryKind::Dense => (Backing::Dense(Vec::new()), DEFAULT_LIMIT),
+            MemoryKind::Paged => (Backing::Paged(HashMap::new()), PAGED_LIMIT),
+        };
+        let mut mem = Memory{backing, len: 0, limit, zero: W::from_i64(0)};
+        mem.load(program);
+        mem
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Memory<W> {
+        self.limit = limit;
+        self
+    }
+
+    pub fn kind(&self) -> MemoryKind {
+        match self.backing {
+            Backing::Dense(_) => MemoryKind::Dense,
+            Backing::Paged(_) => MemoryKind::Paged,
+        }
+    }
+
+    fn new_page(&self) -> Box<[W]> {
+        vec![self.zero.clone(); PAGE_SIZE].into_boxed_slice()
+    }
+
+    /// Replace the contents with `program`, keeping the backend and limit.
+    pub fn load(&mut self, program: &[W]) {
+        let empty = self.new_page();
+        match &mut self.backing {
+            Backing::Dense(v) => {
+                v.clear();
+                v.extend_from_slice(program);
+            },
+            Backing::Paged(pages) => {
+                pages.clear();
+                for (i, chunk) in program.chunks(PAGE_SIZE).enumerate() {
+                    let mut page = empty.clone();
+                    page[..chunk.len()].clone_from_slice(chunk);
+                    pages.insert(i, page);
+                }
+            },
+        }
+        self.len = program.len();
+    }
+
+    /// One past the highest address written, or the program length.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The word at `addr`, or `None` past the end of memory.
+    pub fn get(&self, addr: usize) -> Option<W> {
+        if addr >= self.len {
+            return None;
+        }
+        Some(self[addr].clone())
+    }
+
+    /// Up to `n` words starting at `addr`, stopping at the end of memory.
+    pub fn window(&self, addr: usize, n: usize) -> Vec<W> {
+        (addr..addr.saturating_add(n)).map_while(|a| self.get(a)).collect()
+    }
+
+    /// Decode the instruction at `addr`. Words that do not fit in a
+    /// `Numeric` cannot be part of an instruction.
+    pub fn decode(&self, addr: usize) -> Option<Instr> {
+        let words: Vec<Numeric> = self.window(addr, 4).iter().map_while(|w| w.to_i64()).collect();
+        let mut instr = Instr::decode(&words, 0)?;
+        instr.addr = addr;
+        Some(instr)
+    }
+
+    pub fn to_vec(&self) -> Vec<W> {
+        match &self.backing {
+            Backing::Dense(v) => v.clone(),
+            Backing::Paged(_) => (0..self.len).map(|a| self[a].clone()).collect(),
+        }
+    }
+
+    fn check(&self, addr: Numeric) -> Result<usize, MemoryError> {
+        if addr < 0 {
+            return Err(MemoryError::Negative(addr));
+        }
+        if addr as u64 >= self.limit as u64 {
+            return Err(MemoryError::OutOfBounds{addr, limit: self.limit});
+        }
+        Ok(addr as usize)
+    }
+
+    /// Read a word; anything past the end of memory reads as zero.
+    pub fn read(&self, addr: Numeric) -> Result<W, MemoryError> {
+        let addr = self.check(addr)?;
+        Ok(self[addr].clone())
+    }
+
+    /// Write a word, growing memory as needed.
+    pub fn write(&mut self, addr: Numeric, val: W) -> Result<(), MemoryError> {
+        let addr = self.check(addr)?;
+        let empty = match &self.backing {
+            Backing::Paged(pages) if !pages.contains_key(&(addr / PAGE_SIZE)) => Some(self.new_page()),
+            _ => None,
+        };
+        match &mut self.backing {
+            Backing::Dense(v) => {
+                if addr >= v.len() {
+                    v.resize(addr+1, self.zero.clone());
+                }
+                v[addr] = val;
+            },
+            Backing::Paged(pages) => {
+                let page = pages.entry(addr / PAGE_SIZE).or_insert_with(|| empty.unwrap());
+                page[addr % PAGE_SIZE] = val;
+            },
+        }
+        self.len = self.len.max(addr+1);
+        Ok(())
+    }
+}
+
+impl<W: Word> Index<usize> for Memory<W> {
+    type Output = W;
+
+    /// Untouched memory reads as zero.
+    fn index(&self, addr: usize) -> &W {
+        match &self.backing {
+            Backing::Dense(v) => v.get(addr).unwrap_or(&self.zero),
+            Backing::Paged(pages) => match pages.get(&(addr / PAGE_SIZE)) {
+                Some(page) => &page[addr % PAGE_SIZE],
+                None => &self.zero,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paged_far_write() {
+        let mut mem = Memory::paged(&[1,2,3]);
+        mem.write(10_000_000, 7).unwrap();
+        assert_eq!(mem.read(10_000_000), Ok(7));
+        assert_eq!(mem.read(5_000_000), Ok(0));
+        assert_eq!(mem.len(), 10_000_001);
+        assert_eq!(mem[1], 2);
+        if let Backing::Paged(pages) = &mem.backing {
+            assert_eq!(pages.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        for kind in [MemoryKind::Dense, MemoryKind::Paged].iter() {
+            let mut mem = Memory::new(*kind, &[99]).with_limit(100);
+            assert_eq!(mem.write(-1, 0), Err(MemoryError::Negative(-1)));
+            assert_eq!(mem.read(100), Err(MemoryError::OutOfBounds{addr: 100, limit: 100}));
+            assert_eq!(mem.write(99, 5), Ok(()));
+            assert_eq!(mem.to_vec().len(), 100);
+            assert_eq!(mem.get(99), Some(5));
+            assert_eq!(mem.get(100), None);
+        }
+        assert_eq!(MemoryError::Negative(-3).to_string(), "negative address -3");
+    }
+
+    #[test]
+    fn test_reads_leave_memory_alone() {
+        for kind in [MemoryKind::Dense, MemoryKind::Paged].iter() {
+            let mem = Memory::new(*kind, &[1,2,3]);
+            assert_eq!(mem.read(5000), Ok(0));
+            assert_eq!(mem.len(), 3);
+            assert_eq!(mem.to_vec(), vec![1,2,3]);
+        }
+        assert_eq!(Memory::paged(&[]).limit, PAGED_LIMIT);
+    }
+
+    #[test]
+    fn test_decode_across_pages() {
+        let mut prog = vec![0; PAGE_SIZE-2];
+        prog.extend_from_slice(&[1101,2,3,0]);
+        let mem = Memory::paged(&prog);
+        assert_eq!(mem.decode(PAGE_SIZE-2), Memory::dense(prog.clone()).decode(PAGE_SIZE-2));
+        assert_eq!(mem.decode(PAGE_SIZE-2).unwrap().addr, PAGE_SIZE-2);
+        assert_eq!(mem.window(PAGE_SIZE, 4), vec![3,0]);
+    }
+}
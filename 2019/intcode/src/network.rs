@@ -0,0 +1,359 @@
+//! Networks of Intcode machines exchanging packets through a router.
+//!
+//! Each machine's address is its index in `Network::machines`. How outputs
+//! become packets is set by the `Topology`: the stream topologies send every
+//! output value on as a one-word packet (the day7 amplifiers), while
+//! `Addressed(n)` reads `[dest, n words...]` from a machine's output (the
+//! day23 network). Packets to an address with no machine go to the NAT if
+//! it has that address, and to `monitor` otherwise.
+//!
+//! Machines run round-robin until each one blocks on input. When a whole
+//! round passes without any packets or pending input, the network is idle;
+//! the NAT then forwards the last packet it received.
+
+use std::collections::HashSet;
+
+use crate::comp::{Event, IntcodeComp};
+use crate::error::IntcodeError;
+use crate::Numeric;
+
+pub type Address = usize;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Packet {
+    pub from: Address,
+    pub to: Address,
+    pub data: Vec<Numeric>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Topology {
+    Chain, // i sends to i+1, the last machine to the monitor
+    Ring, // i sends to i+1, the last machine back to 0
+    Star(Address), // the hub sends to everyone, everyone else to the hub
+    Broadcast, // everyone sends to everyone else
+    Addressed(usize), // outputs are a destination and this many words
+}
+
+/// The NAT keeps the last packet sent to it and forwards it when the
+/// network goes idle.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Nat {
+    pub address: Address, // packets to this address are kept
+    pub target: Address, // where to send the last packet when idle
+    pub last: Option<Packet>, // last packet received
+    pub sent: Vec<Packet>, // everything forwarded so far
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum NetEvent {
+    Halted, // every machine halted
+    Idle, // nothing is happening and the NAT has nothing to send
+    NatSent(Packet), // the network was idle and the NAT woke it up
+}
+
+pub struct Network {
+    pub machines: Vec<IntcodeComp>,
+    pub topology: Topology,
+    pub idle_input: Option<Numeric>, // fed to a machine waiting on an empty queue
+    pub nat: Option<Nat>,
+    pub monitor: Vec<Packet>, // packets that left the network
+    pub last: Vec<Option<Packet>>, // last packet sent by each machine
+    outbox: Vec<Vec<Numeric>>, // output not yet forming a whole packet
+    halted: HashSet<Address>,
+}
+
+impl Network {
+    pub fn new(machines: Vec<IntcodeComp>, topology: Topology) -> Network {
+        let n = machines.len();
+        Network{
+            machines,
+            topology,
+            idle_input: None,
+            nat: None,
+            monitor: Vec::new(),
+            last: vec![None; n],
+            outbox: vec![Vec::new(); n],
+            halted: HashSet::new(),
+        }
+    }
+
+    /// Run `n` copies of `program`, each given its address as first input.
+    pub fn from_program(program: &[Numeric], n: usize, topology: Topology) -> Network {
+        let machines = (0..n).map(|i| {
+            let mut comp = IntcodeComp::new(i, program.to_vec());
+            comp.push_input(i as Numeric);
+            comp
+        }).collect();
+        Network::new(machines, topology)
+    }
+
+    pub fn with_idle_input(mut self, val: Numeric) -> Network {
+        self.idle_input = Some(val);
+        self
+    }
+
+    pub fn with_nat(mut self, address: Address, target: Address) -> Network {
+        self.nat = Some(Nat{address, target, last: None, sent: Vec::new()});
+        self
+    }
+
+    /// Where a one-word packet from `from` goes in a stream topology.
+    fn links(&self, from: Address) -> Vec<Address> {
+        let n = self.machines.len();
+        match self.topology {
+            Topology::Chain => vec![from+1],
+            Topology::Ring => vec![(from+1) % n],
+            Topology::Star(hub) if from == hub => (0..n).filter(|a| *a != hub).collect(),
+            Topology::Star(hub) => vec![hub],
+            Topology::Broadcast => (0..n).filter(|a| *a != from).collect(),
+            Topology::Addressed(_) => Vec::new(),
+        }
+    }
+
+    /// Deliver a packet to a machine, the NAT or the monitor.
+    pub fn send(&mut self, packet: Packet) {
+        if let Some(comp) = self.machines.get_mut(packet.to) {
+            comp.input.extend(packet.data.iter().copied());
+            return;
+        }
+        match &mut self.nat {
+            Some(nat) if nat.address == packet.to => nat.last = Some(packet),
+            _ => self.monitor.push(packet),
+        }
+    }
+
+    /// Route any complete packets in `from`'s outbox, returning how many.
+    fn route(&mut self, from: Address) -> usize {
+        let mut sent = 0;
+        let packets = match self.topology {
+            Topology::Addressed(words) => {
+                let mut packets = Vec::new();
+                while self.outbox[from].len() > words {
+                    let mut data: Vec<Numeric> = self.outbox[from].drain(..=words).collect();
+                    let to = data.remove(0);
+                    if to < 0 {
+                        continue; // nowhere to go
+                    }
+                    packets.push(Packet{from, to: to as Address, data});
+                }
+                packets
+            },
+            _ => {
+                let data: Vec<Numeric> = self.outbox[from].drain(..).collect();
+                let mut packets = Vec::new();
+                for val in data {
+                    for to in self.links(from) {
+                        packets.push(Packet{from, to, data: vec![val]});
+                    }
+                }
+                packets
+            },
+        };
+        for p in packets {
+            self.last[from] = Some(p.clone());
+            self.send(p);
+            sent += 1;
+        }
+        sent
+    }
+
+    /// Whether no running machine has input waiting; what is queued for a
+    /// halted machine is never read.
+    fn queues_empty(&self) -> bool {
+        self.machines.iter().enumerate()
+            .all(|(addr, m)| m.input.is_empty() || self.halted.contains(&addr))
+    }
+
+    /// Run each machine once until it blocks or halts, returning the number
+    /// of packets sent.
+    pub fn round(&mut self) -> Result<usize, IntcodeError> {
+        let mut sent = 0;
+        for addr in 0..self.machines.len() {
+            if self.halted.contains(&addr) {
+                continue;
+            }
+            let mut fed = false;
+            loop {
+                match self.machines[addr].run_until_event()? {
+                    Event::Output(val) => {
+                        self.outbox[addr].push(val);
+                        sent += self.route(addr);
+                    },
+                    Event::NeedsInput => match self.idle_input {
+                        Some(val) if !fed => {
+                            self.machines[addr].push_input(val);
+                            fed = true;
+                        },
+                        _ => break,
+                    },
+                    Event::Halted => {
+                        self.halted.insert(addr);
+                        break;
+                    },
+                }
+            }
+        }
+        Ok(sent)
+    }
+
+    pub fn all_halted(&self) -> bool {
+        self.halted.len() == self.machines.len()
+    }
+
+    /// Run until every machine halts, or until the network is idle. An idle
+    /// network with a NAT holding a packet is woken up by it instead.
+    pub fn run_until_event(&mut self) -> Result<NetEvent, IntcodeError> {
+        loop {
+            let pending = !self.queues_empty();
+            let sent = self.round()?;
+            if self.all_halted() {
+                return Ok(NetEvent::Halted);
+            }
+            if pending || sent > 0 || !self.queues_empty() {
+                continue;
+            }
+            let wake = match &mut self.nat {
+                Some(nat) => nat.last.take().map(|p| Packet{from: nat.address, to: nat.target, data: p.data}),
+                None => None,
+            };
+            match wake {
+                Some(p) => {
+                    if let Some(nat) = &mut self.nat {
+                        nat.sent.push(p.clone());
+                    }
+                    self.send(p.clone());
+                    return Ok(NetEvent::NatSent(p));
+                },
+                None => return Ok(NetEvent::Idle),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::str_to_vec;
+
+    fn amplifiers(program: &str, phases: &[Numeric], topology: Topology) -> Network {
+        let machines = phases.iter().enumerate().map(|(i, p)| {
+            let mut comp = IntcodeComp::new(i, str_to_vec(program));
+            comp.push_input(*p);
+            comp
+        }).collect();
+        let mut net = Network::new(machines, topology);
+        net.machines[0].push_input(0);
+        net
+    }
+
+    #[test]
+    fn test_chain() {
+        let prog = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        let mut net = amplifiers(prog, &[4,3,2,1,0], Topology::Chain);
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Halted));
+        assert_eq!(net.monitor, vec![Packet{from: 4, to: 5, data: vec![43210]}]);
+    }
+
+    #[test]
+    fn test_ring() {
+        let prog = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+        let mut net = amplifiers(prog, &[9,8,7,6,5], Topology::Ring);
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Halted));
+        assert_eq!(net.last[4].as_ref().unwrap().data, vec![139629729]);
+    }
+
+    #[test]
+    fn test_star_and_broadcast() {
+        // every machine outputs its input plus its address, then halts
+        let prog = assemble("in -> [x]\nin -> [x]\nadd [x], [a], [x]\nout [x]\nhalt\nx: data 0\na: data 0").unwrap();
+        let mut net = Network::from_program(&prog, 3, Topology::Star(0));
+        for (i, m) in net.machines.iter_mut().enumerate() {
+            m.set_mem(prog.len()-1, i as Numeric).unwrap();
+        }
+        net.machines[0].push_input(100);
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Halted));
+        assert_eq!(net.last[1].as_ref().unwrap().data, vec![101]);
+        assert_eq!(net.last[2].as_ref().unwrap().data, vec![102]);
+        assert_eq!(net.machines[0].input.iter().copied().collect::<Vec<_>>(), vec![101, 102]);
+
+        let mut net = Network::from_program(&prog, 3, Topology::Broadcast);
+        for (i, m) in net.machines.iter_mut().enumerate() {
+            m.set_mem(prog.len()-1, i as Numeric).unwrap();
+        }
+        net.machines[2].push_input(7);
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Halted));
+        assert_eq!(net.last[2].as_ref().unwrap().to, 1);
+        assert_eq!(net.last[1].as_ref().unwrap().data, vec![10]);
+        assert_eq!(net.machines[1].input.iter().copied().collect::<Vec<_>>(), vec![9]);
+    }
+
+    #[test]
+    fn test_send_to_halted() {
+        // the hub sends 5 and halts; each leaf echoes what it reads back to
+        // the hub, then reads again
+        let star = || {
+            let leaf = vec![3,10,4,10,3,10,99,0,0,0,0];
+            let machines = vec![IntcodeComp::new(0, vec![104,5,99]),
+                                IntcodeComp::new(1, leaf.clone()), IntcodeComp::new(2, leaf)];
+            Network::new(machines, Topology::Star(0))
+        };
+        let mut net = star();
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Idle));
+        assert_eq!(net.machines[0].input.iter().copied().collect::<Vec<_>>(), vec![5, 5]);
+
+        // given their addresses first, the leaves read the hub's 5 and halt
+        let mut net = star();
+        for (addr, m) in net.machines.iter_mut().enumerate() {
+            m.push_input(addr as Numeric);
+        }
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Halted));
+    }
+
+    const FORWARDER: &str = "
+            in -> [a]
+            jt [a], #loop
+            out #1          ; machine 0 starts things off
+            out #10
+            out #20
+    loop:   in -> [x]
+            eq [x], #-1 -> [t]
+            jt [t], #loop
+            in -> [y]
+            add [y], #1 -> [y]
+            eq [a], #2 -> [t]
+            jt [t], #nat
+            add [a], #1 -> [d]
+            out [d]
+            jf #0, #send
+    nat:    out #255
+    send:   out [x]
+            out [y]
+            jt #1, #loop
+    a:      data 0
+    x:      data 0
+    y:      data 0
+    t:      data 0
+    d:      data 0
+    ";
+
+    #[test]
+    fn test_nat() {
+        let prog = assemble(FORWARDER).unwrap();
+        let mut net = Network::from_program(&prog, 3, Topology::Addressed(2))
+            .with_idle_input(-1)
+            .with_nat(255, 0);
+        assert_eq!(net.run_until_event(), Ok(NetEvent::NatSent(Packet{from: 255, to: 0, data: vec![10, 22]})));
+        assert_eq!(net.run_until_event(), Ok(NetEvent::NatSent(Packet{from: 255, to: 0, data: vec![10, 25]})));
+        assert_eq!(net.nat.unwrap().sent.len(), 2);
+    }
+
+    #[test]
+    fn test_idle_without_nat() {
+        let prog = assemble(FORWARDER).unwrap();
+        let mut net = Network::from_program(&prog, 3, Topology::Addressed(2)).with_idle_input(-1);
+        assert_eq!(net.run_until_event(), Ok(NetEvent::Idle));
+        assert_eq!(net.monitor, vec![Packet{from: 2, to: 255, data: vec![10, 22]}]);
+    }
+}
@@ -0,0 +1,240 @@
+//! The Intcode instruction set: opcodes, parameter modes and decoding.
+
+use std::fmt;
+
+use crate::comp::IntcodeComp;
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Op {
+    Add = 1,
+    Mul = 2,
+    In = 3,
+    Out = 4,
+    Jt = 5,
+    Jf = 6,
+    Lt = 7,
+    Eq = 8,
+    Arb = 9,
+    Halt = 99,
+}
+
+impl Op {
+    pub const ALL: [Op; 10] = [Op::Add, Op::Mul, Op::In, Op::Out, Op::Jt,
+                               Op::Jf, Op::Lt, Op::Eq, Op::Arb, Op::Halt];
+
+    pub fn from_code(code: Numeric) -> Option<Op> {
+        match code {
+            1 => Some(Op::Add),
+            2 => Some(Op::Mul),
+            3 => Some(Op::In),
+            4 => Some(Op::Out),
+            5 => Some(Op::Jt),
+            6 => Some(Op::Jf),
+            7 => Some(Op::Lt),
+            8 => Some(Op::Eq),
+            9 => Some(Op::Arb),
+            99 => Some(Op::Halt),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> Numeric {
+        self as Numeric
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Op::Add => "ADD",
+            Op::Mul => "MUL",
+            Op::In => "IN",
+            Op::Out => "OUT",
+            Op::Jt => "JT",
+            Op::Jf => "JF",
+            Op::Lt => "LT",
+            Op::Eq => "EQ",
+            Op::Arb => "ARB",
+            Op::Halt => "HALT",
+        }
+    }
+
+    /// Number of parameters following the instruction word.
+    pub fn num_params(self) -> usize {
+        match self {
+            Op::Add | Op::Mul | Op::Lt | Op::Eq => 3,
+            Op::Jt | Op::Jf => 2,
+            Op::In | Op::Out | Op::Arb => 1,
+            Op::Halt => 0,
+        }
+    }
+
+    /// Whether the last parameter is a write target.
+    pub fn writes(self) -> bool {
+        matches!(self, Op::Add | Op::Mul | Op::Lt | Op::Eq | Op::In)
+    }
+
+    pub fn is_jump(self) -> bool {
+        matches!(self, Op::Jt | Op::Jf)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Mode {
+    Position = 0,
+    Immediate = 1,
+    Relative = 2,
+}
+
+impl Mode {
+    pub fn from_code(code: Numeric) -> Option<Mode> {
+        match code {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Param {
+    pub mode: Mode,
+    pub value: Numeric,
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            Mode::Position => write!(f, "[{}]", self.value),
+            Mode::Immediate => write!(f, "#{}", self.value),
+            Mode::Relative if self.value < 0 => write!(f, "[rb{}]", self.value),
+            Mode::Relative => write!(f, "[rb+{}]", self.value),
+        }
+    }
+}
+
+/// A single decoded instruction.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Instr {
+    pub addr: usize, // address of the instruction word
+    pub op: Op,
+    pub params: Vec<Param>,
+}
+
+impl Instr {
+    /// Decode the instruction at `addr`, or `None` if the word there is not
+    /// a valid instruction (bad opcode, bad mode, immediate write target, or
+    /// running off the end of the program).
+    pub fn decode(program: &[Numeric], addr: usize) -> Option<Instr> {
+        let instr = *program.get(addr)?;
+        if instr < 0 {
+            return None;
+        }
+        let op = Op::from_code(instr % 100)?;
+        let modes = IntcodeComp::get_modes(instr);
+        let modes = [modes.0, modes.1, modes.2];
+        if instr / 100000 != 0 || modes[op.num_params()..].iter().any(|m| *m != 0) {
+            return None; // mode digits for parameters that don't exist
+        }
+        let mut params = Vec::new();
+        for (i, m) in modes.iter().take(op.num_params()).enumerate() {
+            let mode = Mode::from_code(*m)?;
+            let value = *program.get(addr+1+i)?;
+            params.push(Param{mode, value});
+        }
+        if op.writes() && params.last().unwrap().mode == Mode::Immediate {
+            return None;
+        }
+        Some(Instr{addr, op, params})
+    }
+
+    /// Length of the instruction in words.
+    pub fn size(&self) -> usize {
+        1 + self.params.len()
+    }
+
+    /// Encode back into program words.
+    pub fn encode(&self) -> Vec<Numeric> {
+        let mut word = self.op.code();
+        let mut scale = 100;
+        for p in self.params.iter() {
+            word += p.mode as Numeric * scale;
+            scale *= 10;
+        }
+        let mut v = vec![word];
+        v.extend(self.params.iter().map(|p| p.value));
+        v
+    }
+
+    /// The jump target if it is known without running the program.
+    pub fn static_target(&self) -> Option<usize> {
+        match self.op {
+            Op::Jt | Op::Jf if self.params[1].mode == Mode::Immediate && self.params[1].value >= 0 =>
+                Some(self.params[1].value as usize),
+            _ => None,
+        }
+    }
+
+    /// Whether execution can never continue with the next instruction.
+    pub fn is_unconditional(&self) -> bool {
+        let cond = self.params.first();
+        match self.op {
+            Op::Halt => true,
+            Op::Jt => cond.is_some_and(|p| p.mode == Mode::Immediate && p.value != 0),
+            Op::Jf => cond.is_some_and(|p| p.mode == Mode::Immediate && p.value == 0),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Instr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.op.mnemonic())?;
+        let (reads, write) = match self.op.writes() {
+            true => self.params.split_at(self.params.len()-1),
+            false => self.params.split_at(self.params.len()),
+        };
+        for (i, p) in reads.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { " " } else { ", " }, p)?;
+        }
+        if let Some(p) = write.first() {
+            write!(f, " -> {}", p)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let prog = vec![22101,5,3,100];
+        let instr = Instr::decode(&prog, 0).unwrap();
+        assert_eq!(instr.op, Op::Add);
+        assert_eq!(instr.params[0], Param{mode: Mode::Immediate, value: 5});
+        assert_eq!(instr.params[2], Param{mode: Mode::Relative, value: 100});
+        assert_eq!(instr.encode(), prog);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(Instr::decode(&[1101,1,2,3], 0).map(|i| i.op), Some(Op::Add));
+        assert_eq!(Instr::decode(&[11101,1,2,3], 0), None); // immediate write
+        assert_eq!(Instr::decode(&[301,1,2,3], 0), None); // bad mode
+        assert_eq!(Instr::decode(&[42], 0), None); // bad opcode
+        assert_eq!(Instr::decode(&[1,2], 0), None); // truncated
+        assert_eq!(Instr::decode(&[199], 0), None); // mode on halt
+    }
+
+    #[test]
+    fn test_display() {
+        let prog = vec![1201,3,5,100];
+        assert_eq!(Instr::decode(&prog, 0).unwrap().to_string(), "ADD [rb+3], #5 -> [100]");
+        let prog = vec![1205,-2,922];
+        assert_eq!(Instr::decode(&prog, 0).unwrap().to_string(), "JT [rb-2], #922");
+        assert_eq!(Instr::decode(&[203,0], 0).unwrap().to_string(), "IN -> [rb+0]");
+        assert_eq!(Instr::decode(&[99], 0).unwrap().to_string(), "HALT");
+    }
+}
@@ -0,0 +1,137 @@
+//! Amplifier phase-setting search (2019 day7).
+//!
+//! Every permutation of the phase set is wired up as a `Network` of
+//! amplifiers, each given its phase as first input and the first given a
+//! signal of 0. The permutations are independent, so they are evaluated in
+//! parallel on the rayon thread pool.
+
+use rayon::prelude::*;
+
+use crate::comp::IntcodeComp;
+use crate::error::IntcodeError;
+use crate::network::{NetEvent, Network, Topology};
+use crate::Numeric;
+
+/// One phase setting and the signal that came out of the last amplifier.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Setting {
+    pub phases: Vec<Numeric>,
+    pub signal: Option<Numeric>, // None if the last amplifier never output
+}
+
+/// Every setting tried, best first.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct PhaseSearch {
+    pub ranked: Vec<Setting>,
+}
+
+impl PhaseSearch {
+    /// The setting with the strongest signal; `None` if nothing was tried.
+    pub fn best(&self) -> Option<&Setting> {
+        self.ranked.first()
+    }
+}
+
+/// All orderings of `set`, in lexicographic order of positions.
+pub fn permutations(set: &[Numeric]) -> Vec<Vec<Numeric>> {
+    if set.len() <= 1 {
+        return vec![set.to_vec()];
+    }
+    let mut perms = Vec::new();
+    for i in 0..set.len() {
+        let mut rest = set.to_vec();
+        let first = rest.remove(i);
+        for mut p in permutations(&rest) {
+            p.insert(0, first);
+            perms.push(p);
+        }
+    }
+    perms
+}
+
+/// The signal out of the last amplifier for one phase setting. Use
+/// `Topology::Chain` for part 1 and `Topology::Ring` for the feedback loop.
+/// With no amplifiers there is no signal.
+pub fn signal(program: &[Numeric], phases: &[Numeric], topology: Topology) -> Result<Option<Numeric>, IntcodeError> {
+    if phases.is_empty() {
+        return Ok(None);
+    }
+    let machines = phases.iter().enumerate().map(|(i, p)| {
+        let mut comp = IntcodeComp::new(i, program.to_vec());
+        comp.push_input(*p);
+        comp
+    }).collect();
+    let mut net = Network::new(machines, topology);
+    net.machines[0].push_input(0);
+    match net.run_until_event()? {
+        NetEvent::Halted | NetEvent::Idle => (),
+        NetEvent::NatSent(_) => unreachable!("amplifiers have no NAT"),
+    }
+    let last = net.last[phases.len()-1].as_ref();
+    Ok(last.and_then(|p| p.data.last().copied()))
+}
+
+/// Try every permutation of `phase_set` in parallel, ranking the results.
+/// An empty set tries nothing.
+pub fn search(program: &[Numeric], phase_set: &[Numeric], topology: Topology) -> Result<PhaseSearch, IntcodeError> {
+    if phase_set.is_empty() {
+        return Ok(PhaseSearch{ranked: Vec::new()});
+    }
+    let mut ranked = permutations(phase_set).into_par_iter()
+        .map(|phases| {
+            let signal = signal(program, &phases, topology)?;
+            Ok(Setting{phases, signal})
+        })
+        .collect::<Result<Vec<Setting>, IntcodeError>>()?;
+    // stable, so equal signals keep permutation order
+    ranked.sort_by_key(|s| std::cmp::Reverse(s.signal));
+    Ok(PhaseSearch{ranked})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::str_to_vec;
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(permutations(&[1,2,3]), vec![
+            vec![1,2,3], vec![1,3,2], vec![2,1,3], vec![2,3,1], vec![3,1,2], vec![3,2,1],
+        ]);
+        assert_eq!(permutations(&[0,1,2,3,4]).len(), 120);
+    }
+
+    #[test]
+    fn test_serial() {
+        let prog = str_to_vec("3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0");
+        assert_eq!(signal(&prog, &[0,1,2,3,4], Topology::Chain), Ok(Some(54321)));
+        let result = search(&prog, &[0,1,2,3,4], Topology::Chain).unwrap();
+        assert_eq!(result.best().unwrap(), &Setting{phases: vec![0,1,2,3,4], signal: Some(54321)});
+        assert_eq!(result.ranked.len(), 120);
+        assert!(result.ranked.windows(2).all(|w| w[0].signal >= w[1].signal));
+    }
+
+    #[test]
+    fn test_feedback() {
+        let prog = str_to_vec("3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,\
+-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,\
+53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10");
+        let result = search(&prog, &[5,6,7,8,9], Topology::Ring).unwrap();
+        assert_eq!(result.best().unwrap(), &Setting{phases: vec![9,7,8,5,6], signal: Some(18216)});
+    }
+
+    #[test]
+    fn test_error() {
+        let err = search(&[3,0,42], &[0,1], Topology::Chain).unwrap_err();
+        assert_eq!(err.location().instr, 42);
+    }
+
+    #[test]
+    fn test_empty_phase_set() {
+        let prog = str_to_vec("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+        assert_eq!(signal(&prog, &[], Topology::Chain), Ok(None));
+        let result = search(&prog, &[], Topology::Ring).unwrap();
+        assert_eq!(result.best(), None);
+        assert!(result.ranked.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+//! Execution profiler for the Intcode VM.
+//!
+//! A `Profiler` attached to an `IntcodeComp` counts how often each address
+//! and each opcode is executed, and how often each cell is read or written
+//! through a parameter. Instruction fetches are not counted as reads.
+//!
+//! `IntcodeComp::with_profiler` attaches one that prints a report of the
+//! hot spots to stderr when the machine halts. `IntcodeComp::new` attaches
+//! one when `INTCODE_PROFILE` is set; its value is the number of hot spots.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+use crate::disasm::{disassemble, format_line, Line};
+use crate::memory::Memory;
+use crate::op::Op;
+use crate::word::Word;
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Profiler {
+    pub addrs: HashMap<usize, u64>, // executions per instruction address
+    pub ops: HashMap<Op, u64>, // executions per opcode
+    pub reads: HashMap<usize, u64>, // parameter reads per cell
+    pub writes: HashMap<usize, u64>, // writes per cell
+    pub report_on_halt: Option<usize>, // hot spots to print when the machine halts
+    halted: bool, // the halt has been counted
+}
+
+/// Hot spots reported by a profiler from `IntcodeComp::with_profiler`.
+pub(crate) const REPORT_TOP: usize = 20;
+
+/// Counts sorted by count, highest first, then by key.
+fn ranked<K: Copy + Ord>(counts: &HashMap<K, u64>) -> Vec<(K, u64)> {
+    let mut v: Vec<(K, u64)> = counts.iter().map(|(k, c)| (*k, *c)).collect();
+    v.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    v
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    /// A profiler that prints the `top` hot spots when the machine halts.
+    pub fn reporting(top: usize) -> Profiler {
+        Profiler{report_on_halt: Some(top), ..Profiler::default()}
+    }
+
+    /// The profiler described by `INTCODE_PROFILE`, if it is set. The
+    /// variable is read once; a bad count is warned about and ignored.
+    pub fn from_env() -> Option<Profiler> {
+        static TOP: OnceLock<Option<usize>> = OnceLock::new();
+        let top = TOP.get_or_init(|| {
+            let var = env::var("INTCODE_PROFILE").ok()?;
+            match var.trim().parse() {
+                Ok(top) => Some(top),
+                Err(_) => {
+                    eprintln!("warning: INTCODE_PROFILE: bad count '{}'; not profiling", var);
+                    None
+                },
+            }
+        });
+        top.map(Profiler::reporting)
+    }
+
+    /// Total instructions executed.
+    pub fn steps(&self) -> u64 {
+        self.addrs.values().sum()
+    }
+
+    pub(crate) fn executed(&mut self, addr: usize, op: Op) {
+        *self.addrs.entry(addr).or_insert(0) += 1;
+        *self.ops.entry(op).or_insert(0) += 1;
+    }
+
+    pub(crate) fn read(&mut self, addr: Numeric) {
+        if addr >= 0 {
+            *self.reads.entry(addr as usize).or_insert(0) += 1;
+        }
+    }
+
+    pub(crate) fn write(&mut self, addr: usize) {
+        *self.writes.entry(addr).or_insert(0) += 1;
+    }
+
+    /// Count the halt at `addr` the first time the machine gets there, and
+    /// print the report to stderr then; halting again runs nothing.
+    pub(crate) fn halt<W: Word>(&mut self, name: usize, addr: usize, mem: &Memory<W>) {
+        if self.halted {
+            return;
+        }
+        self.halted = true;
+        self.executed(addr, Op::Halt);
+        if let Some(top) = self.report_on_halt {
+            eprint!("machine {} profile:\n{}", name, self.report(mem, top));
+        }
+    }
+
+    /// The `top` hottest addresses with their instructions, every opcode,
+    /// and the `top` busiest memory cells.
+    pub fn report<W: Word>(&self, mem: &Memory<W>, top: usize) -> String {
+        let mut out = String::new();
+        let steps = self.steps();
+        writeln!(out, "{} instructions executed", steps).unwrap();
+        writeln!(out, "hot spots:").unwrap();
+        for (addr, count) in ranked(&self.addrs).into_iter().take(top) {
+            let text = mem.decode(addr).map_or("?".to_string(), |i| i.to_string());
+            writeln!(out, "{:>12} {:5.1}%  {:04}: {}", count, 100.0 * count as f64 / steps as f64, addr, text).unwrap();
+        }
+        writeln!(out, "opcodes:").unwrap();
+        let mut ops: Vec<(Op, u64)> = self.ops.iter().map(|(k, c)| (*k, *c)).collect();
+        ops.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.code().cmp(&b.0.code())));
+        for (op, count) in ops {
+            writeln!(out, "{:>12}  {}", count, op.mnemonic()).unwrap();
+        }
+        writeln!(out, "memory (reads, writes):").unwrap();
+        let mut cells = self.reads.clone();
+        for (addr, count) in self.writes.iter() {
+            *cells.entry(*addr).or_insert(0) += count;
+        }
+        for (addr, _) in ranked(&cells).into_iter().take(top) {
+            let r = self.reads.get(&addr).copied().unwrap_or(0);
+            let w = self.writes.get(&addr).copied().unwrap_or(0);
+            writeln!(out, "{:>12} {:>12}  [{}]", r, w, addr).unwrap();
+        }
+        out
+    }
+
+    /// A disassembly of `program` with execution counts next to code and
+    /// read/write counts next to data.
+    pub fn annotate(&self, program: &[Numeric]) -> String {
+        let mut out = String::new();
+        for line in disassemble(program).iter() {
+            let note = match line {
+                Line::Code(i) => match self.addrs.get(&i.addr) {
+                    Some(c) => c.to_string(),
+                    None => String::new(),
+                },
+                Line::Data{addr, values} => {
+                    let cells = *addr..addr+values.len();
+                    let r: u64 = cells.clone().filter_map(|a| self.reads.get(&a)).sum();
+                    let w: u64 = cells.filter_map(|a| self.writes.get(&a)).sum();
+                    match r + w {
+                        0 => String::new(),
+                        _ => format!("r{} w{}", r, w),
+                    }
+                },
+            };
+            writeln!(out, "{:>12}  {}", note, format_line(line)).unwrap();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::IntcodeComp;
+    use crate::str_to_vec;
+
+    #[test]
+    fn test_counts() {
+        // counts [9] down from 3 to 0
+        let prog = str_to_vec("1001,9,-1,9,1005,9,0,99,0,3");
+        let mut comp = IntcodeComp::new(0, prog.clone()).with_profiler();
+        assert_eq!(comp.run_to_halt(), Ok(vec![]));
+        let p = comp.profiler.as_ref().unwrap();
+        assert_eq!(p.addrs[&0], 3);
+        assert_eq!(p.addrs[&4], 3);
+        assert_eq!(p.addrs[&7], 1);
+        assert_eq!(p.ops[&Op::Add], 3);
+        assert_eq!(p.steps(), 7);
+        assert_eq!(p.report_on_halt, Some(REPORT_TOP));
+        assert_eq!(p.reads[&9], 6);
+        assert_eq!(p.writes[&9], 3);
+
+        let report = p.report(&comp.instrs, 2);
+        assert!(report.starts_with("7 instructions executed\nhot spots:\n"));
+        assert!(report.contains("           3  42.9%  0000: ADD [9], #-1 -> [9]\n"));
+        assert!(report.contains("           6            3  [9]\n"));
+
+        let listing = p.annotate(&prog);
+        assert!(listing.contains("           3  0004: JT [9], #0\n"));
+        assert!(listing.contains("       r6 w3  0008: DATA 0, 3\n"));
+
+        // stepping a halted machine runs nothing more
+        assert_eq!(comp.step(), Ok(Some(crate::comp::Event::Halted)));
+        assert_eq!(comp.profiler.as_ref().unwrap().steps(), 7);
+        assert_eq!(comp.profiler.as_ref().unwrap().addrs[&7], 1);
+    }
+}
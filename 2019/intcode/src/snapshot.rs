@@ -0,0 +1,227 @@
+//! Snapshots of a machine mid-execution.
+//!
+//! A `Snapshot` holds memory, `ptr`, `rel_base` and any queued input. The
+//! memory is shared, so snapshots clone in constant time and a search can
+//! keep as many as it likes; `IntcodeComp::restore` copies it back out,
+//! backend and limit included.
+//! Snapshots also save to a small text file for checkpointing long runs,
+//! which keeps the backend and limit too:
+//!
+//! ```text
+//! intcode-snapshot 2
+//! name 0
+//! ptr 42
+//! rel_base 7
+//! input 1,2
+//! backend paged
+//! limit 16777216
+//! memory 109,1,204,-1,...
+//! ```
+//!
+//! Version 1 files, without the backend and limit, load as dense memory
+//! with the default limit.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::comp::IntcodeComp;
+use crate::memory::{Memory, MemoryKind, DEFAULT_LIMIT};
+use crate::Numeric;
+
+const HEADER: &str = "intcode-snapshot 2";
+const HEADER_V1: &str = "intcode-snapshot 1";
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Snapshot {
+    pub name: usize, // name of the machine it came from
+    pub instrs: Arc<Memory>, // memory, shared between clones
+    pub ptr: usize,
+    pub rel_base: Numeric,
+    pub input: Vec<Numeric>, // input queued but not yet read
+}
+
+impl IntcodeComp {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot{
+            name: self.name,
+            instrs: Arc::new(self.instrs.clone()),
+            ptr: self.ptr,
+            rel_base: self.rel_base,
+            input: self.input.iter().copied().collect(),
+        }
+    }
+
+    /// Put the machine back in the state of `snap`, keeping its tracer and profiler.
+    pub fn restore(&mut self, snap: &Snapshot) {
+        self.name = snap.name;
+        self.instrs = (*snap.instrs).clone();
+        self.ptr = snap.ptr;
+        self.rel_base = snap.rel_base;
+        self.input = snap.input.iter().copied().collect();
+    }
+
+    pub fn from_snapshot(snap: &Snapshot) -> IntcodeComp {
+        let mut comp = IntcodeComp::new(snap.name, Vec::new());
+        comp.restore(snap);
+        comp
+    }
+}
+
+impl Snapshot {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Snapshot> {
+        fs::read_to_string(path)?.parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn join(vals: &[Numeric]) -> String {
+    let v: Vec<String> = vals.iter().map(|x| x.to_string()).collect();
+    v.join(",")
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "name {}", self.name)?;
+        writeln!(f, "ptr {}", self.ptr)?;
+        writeln!(f, "rel_base {}", self.rel_base)?;
+        writeln!(f, "input {}", join(&self.input))?;
+        let backend = match self.instrs.kind() {
+            MemoryKind::Dense => "dense",
+            MemoryKind::Paged => "paged",
+        };
+        writeln!(f, "backend {}", backend)?;
+        writeln!(f, "limit {}", self.instrs.limit)?;
+        writeln!(f, "memory {}", join(&self.instrs.to_vec()))
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Snapshot, String> {
+        let mut lines = s.lines();
+        let v1 = match lines.next().map(|l| l.trim()) {
+            Some(HEADER) => false,
+            Some(HEADER_V1) => true,
+            _ => return Err("not an intcode snapshot".to_string()),
+        };
+        let mut field = |key: &str| -> Result<String, String> {
+            let line = lines.next().ok_or(format!("missing {}", key))?;
+            match line.split_once(' ') {
+                Some((k, v)) if k == key => Ok(v.trim().to_string()),
+                None if line.trim() == key => Ok(String::new()),
+                _ => Err(format!("expected {}, got '{}'", key, line)),
+            }
+        };
+        fn num<T: FromStr>(key: &str, v: &str) -> Result<T, String> {
+            v.parse().map_err(|_| format!("bad {} '{}'", key, v))
+        }
+        fn nums(key: &str, v: &str) -> Result<Vec<Numeric>, String> {
+            v.split(',').filter(|x| !x.trim().is_empty()).map(|x| num(key, x.trim())).collect()
+        }
+        let name = num("name", &field("name")?)?;
+        let ptr = num("ptr", &field("ptr")?)?;
+        let rel_base = num("rel_base", &field("rel_base")?)?;
+        let input = nums("input", &field("input")?)?;
+        let (kind, limit) = match v1 {
+            true => (MemoryKind::Dense, DEFAULT_LIMIT),
+            false => {
+                let kind = match field("backend")?.as_str() {
+                    "dense" => MemoryKind::Dense,
+                    "paged" => MemoryKind::Paged,
+                    k => return Err(format!("bad backend '{}'", k)),
+                };
+                (kind, num("limit", &field("limit")?)?)
+            },
+        };
+        let memory = nums("memory", &field("memory")?)?;
+        let instrs = Arc::new(Memory::new(kind, &memory).with_limit(limit));
+        Ok(Snapshot{name, instrs, ptr, rel_base, input})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::Event;
+
+    // counts up from its input, outputting each value until it reaches 5
+    const COUNTER: [Numeric; 16] = [3,16,4,16,1001,16,1,16,1007,16,6,17,1005,17,2,99];
+
+    #[test]
+    fn test_fork() {
+        let mut comp = IntcodeComp::new(0, COUNTER.to_vec());
+        comp.push_input(1);
+        assert_eq!(comp.run_until_event(), Ok(Event::Output(1)));
+        let snap = comp.snapshot();
+        let rest = comp.run_to_halt().unwrap();
+        assert_eq!(rest, vec![2,3,4,5]);
+
+        // the fork replays the same tail, and is unaffected by the original
+        let mut fork = IntcodeComp::from_snapshot(&snap);
+        assert_eq!(fork.run_to_halt().unwrap(), rest);
+        comp.restore(&snap.clone());
+        comp.set_mem(16, 3).unwrap();
+        assert_eq!(comp.run_to_halt().unwrap(), vec![4,5]);
+    }
+
+    #[test]
+    fn test_pending_input() {
+        let mut comp = IntcodeComp::new(2, COUNTER.to_vec());
+        comp.push_input(4);
+        comp.push_input(99);
+        let snap = comp.snapshot();
+        assert_eq!(snap.input, vec![4, 99]);
+        let restored = IntcodeComp::from_snapshot(&snap);
+        assert_eq!(restored.input.iter().copied().collect::<Vec<_>>(), vec![4, 99]);
+        assert_eq!(restored.name, 2);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut comp = IntcodeComp::new(0, COUNTER.to_vec());
+        comp.push_input(2);
+        comp.run_until_event().unwrap();
+        comp.rel_base = -3;
+        let snap = comp.snapshot();
+        let path = std::env::temp_dir().join(format!("intcode-snapshot-{}.txt", std::process::id()));
+        snap.save(&path).unwrap();
+        let loaded = Snapshot::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, snap);
+        assert_eq!(IntcodeComp::from_snapshot(&loaded).run_to_halt().unwrap(), vec![3,4,5]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("hello".parse::<Snapshot>().is_err());
+        let text = format!("{}\nname 0\nptr x\n", HEADER);
+        assert_eq!(text.parse::<Snapshot>(), Err("bad ptr 'x'".to_string()));
+        let text = format!("{}\nname 0\nptr 0\nrel_base 0\ninput\nbackend tape\n", HEADER);
+        assert_eq!(text.parse::<Snapshot>(), Err("bad backend 'tape'".to_string()));
+        let text = format!("{}\nname 0\nptr 0\nrel_base 0\ninput\nmemory 99\n", HEADER_V1);
+        let snap = text.parse::<Snapshot>().unwrap();
+        assert_eq!(snap.instrs.to_vec(), vec![99]);
+        assert_eq!(snap.instrs.kind(), MemoryKind::Dense);
+    }
+
+    #[test]
+    fn test_save_backend() {
+        let mut comp = IntcodeComp::with_memory(0, Memory::paged(&COUNTER).with_limit(1000));
+        comp.push_input(3);
+        comp.run_until_event().unwrap();
+        let loaded: Snapshot = comp.snapshot().to_string().parse().unwrap();
+        assert_eq!(loaded.instrs.kind(), MemoryKind::Paged);
+        assert_eq!(loaded.instrs.limit, 1000);
+        assert_eq!(loaded, comp.snapshot());
+    }
+}
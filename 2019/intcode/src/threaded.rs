@@ -0,0 +1,311 @@
+//! Pre-decoded execution backend.
+//!
+//! `ThreadedComp` decodes the program once into a table of compact
+//! instructions indexed by address, so the hot loop dispatches on an `Op`
+//! with its modes and arguments already split out instead of re-deriving
+//! them from the instruction word on every step. Addresses are decoded
+//! lazily the first time execution reaches them.
+//!
+//! A write into the words of a decoded instruction makes that address fall
+//! back to `IntcodeComp::step` for good, so self-modifying programs behave
+//! exactly as they do under the interpreter. So does anything unusual: a
+//! memory fault, running off the end, an active tracer or profiler. Those are
+//! handed to the interpreter, which produces the same error, trace or counts
+//! it always would.
+
+use crate::comp::{Event, IntcodeComp};
+use crate::error::IntcodeError;
+use crate::memory::MemoryError;
+use crate::op::{Mode, Op};
+use crate::Numeric;
+
+#[derive(Debug, Clone, Copy)]
+struct Compact {
+    op: Op,
+    modes: [Mode; 3],
+    args: [Numeric; 3],
+}
+
+impl Compact {
+    fn size(&self) -> usize {
+        1 + self.op.num_params()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Unknown, // not reached yet
+    Code(Compact),
+    Interpret, // modified since it was decoded, or not a valid instruction
+}
+
+pub struct ThreadedComp {
+    comp: IntcodeComp,
+    code: Vec<Slot>,
+    pub fallbacks: usize, // instructions handed to the interpreter
+}
+
+impl ThreadedComp {
+    pub fn new(comp: IntcodeComp) -> ThreadedComp {
+        let code = vec![Slot::Unknown; comp.instrs.len()];
+        ThreadedComp{comp, code, fallbacks: 0}
+    }
+
+    pub fn from_program(name: usize, program: Vec<Numeric>) -> ThreadedComp {
+        ThreadedComp::new(IntcodeComp::new(name, program))
+    }
+
+    pub fn comp(&self) -> &IntcodeComp {
+        &self.comp
+    }
+
+    pub fn into_comp(self) -> IntcodeComp {
+        self.comp
+    }
+
+    pub fn push_input(&mut self, val: Numeric) {
+        self.comp.push_input(val);
+    }
+
+    /// Write memory from outside the program, like `IntcodeComp::set_mem`.
+    pub fn set_mem(&mut self, index: usize, val: Numeric) -> Result<(), MemoryError> {
+        self.comp.set_mem(index, val)?;
+        self.invalidate(index);
+        Ok(())
+    }
+
+    /// Stop trusting any decoded instruction covering `addr`.
+    fn invalidate(&mut self, addr: usize) {
+        for a in addr.saturating_sub(3)..addr.saturating_add(1).min(self.code.len()) {
+            if let Slot::Code(c) = self.code[a] {
+                if a + c.size() > addr {
+                    self.code[a] = Slot::Interpret;
+                }
+            }
+        }
+    }
+
+    fn slot(&mut self, ptr: usize) -> Slot {
+        if ptr >= self.code.len() {
+            self.code.resize(self.comp.instrs.len().max(ptr+1), Slot::Unknown);
+        }
+        if let Slot::Unknown = self.code[ptr] {
+            self.code[ptr] = match self.comp.instrs.decode(ptr) {
+                Some(instr) => {
+                    let mut c = Compact{op: instr.op, modes: [Mode::Position; 3], args: [0; 3]};
+                    for (i, p) in instr.params.iter().enumerate() {
+                        c.modes[i] = p.mode;
+                        c.args[i] = p.value;
+                    }
+                    Slot::Code(c)
+                },
+                None => Slot::Interpret,
+            };
+        }
+        self.code[ptr]
+    }
+
+    /// Run one instruction on the interpreter.
+    fn interpret(&mut self) -> Result<Option<Event>, IntcodeError> {
+        self.fallbacks += 1;
+        let target = self.comp.write_target();
+        let result = self.comp.step();
+        if let Some(addr) = target {
+            if addr >= 0 {
+                self.invalidate(addr as usize);
+            }
+        }
+        result
+    }
+
+    /// A parameter's value, or `None` if reading it faults.
+    fn param(&self, c: &Compact, i: usize) -> Option<Numeric> {
+        let addr = match c.modes[i] {
+            Mode::Position => c.args[i],
+            Mode::Immediate => return Some(c.args[i]),
+            Mode::Relative => self.comp.rel_base.checked_add(c.args[i])?,
+        };
+        self.comp.instrs.read(addr).ok()
+    }
+
+    fn store(&mut self, c: &Compact, val: Numeric) -> Option<()> {
+        let i = c.op.num_params()-1;
+        let addr = match c.modes[i] {
+            Mode::Relative => self.comp.rel_base.checked_add(c.args[i])?,
+            _ => c.args[i],
+        };
+        self.comp.instrs.write(addr, val).ok()?;
+        self.invalidate(addr as usize);
+        Some(())
+    }
+
+    /// Execute a single instruction, with the same results as
+    /// `IntcodeComp::step`.
+    pub fn step(&mut self) -> Result<Option<Event>, IntcodeError> {
+        let ptr = self.comp.ptr;
+        if ptr >= self.comp.instrs.len() || self.comp.tracer.is_active() || self.comp.profiler.is_some() {
+            return self.interpret();
+        }
+        let c = match self.slot(ptr) {
+            Slot::Code(c) => c,
+            _ => return self.interpret(),
+        };
+        match self.fast(&c) {
+            Some(event) => Ok(event),
+            None => self.interpret(),
+        }
+    }
+
+    /// The fast path; `None` means the interpreter has to take over, with
+    /// nothing changed.
+    fn fast(&mut self, c: &Compact) -> Option<Option<Event>> {
+        let ptr = self.comp.ptr;
+        match c.op {
+            Op::Add | Op::Mul | Op::Lt | Op::Eq => {
+                let x = self.param(c, 0)?;
+                let y = self.param(c, 1)?;
+                let val = match c.op {
+                    Op::Add => x.checked_add(y)?,
+                    Op::Mul => x.checked_mul(y)?,
+                    Op::Lt => (x < y) as Numeric,
+                    _ => (x == y) as Numeric,
+                };
+                self.store(c, val)?;
+                self.comp.ptr = ptr + 4;
+            },
+            Op::In => {
+                let val = match self.comp.input.front() {
+                    Some(x) => *x,
+                    None => return Some(Some(Event::NeedsInput)),
+                };
+                self.store(c, val)?;
+                self.comp.input.pop_front();
+                self.comp.ptr = ptr + 2;
+            },
+            Op::Out => {
+                let val = self.param(c, 0)?;
+                self.comp.ptr = ptr + 2;
+                return Some(Some(Event::Output(val)));
+            },
+            Op::Jt | Op::Jf => {
+                let x = self.param(c, 0)?;
+                let y = self.param(c, 1)?;
+                if (x != 0) == (c.op == Op::Jt) {
+                    if y < 0 {
+                        return None;
+                    }
+                    self.comp.ptr = y as usize;
+                } else {
+                    self.comp.ptr = ptr + 3;
+                }
+            },
+            Op::Arb => {
+                self.comp.rel_base = self.comp.rel_base.checked_add(self.param(c, 0)?)?;
+                self.comp.ptr = ptr + 2;
+            },
+            Op::Halt => return Some(Some(Event::Halted)),
+        }
+        Some(None)
+    }
+
+    /// Step until the program needs input, produces output, or halts.
+    pub fn run_until_event(&mut self) -> Result<Event, IntcodeError> {
+        loop {
+            if let Some(e) = self.step()? {
+                return Ok(e);
+            }
+        }
+    }
+
+    /// Run to halt on the queued input, returning everything output.
+    pub fn run_to_halt(&mut self) -> Result<Vec<Numeric>, IntcodeError> {
+        let mut output = Vec::new();
+        loop {
+            match self.run_until_event()? {
+                Event::Output(x) => output.push(x),
+                Event::NeedsInput => return Err(IntcodeError::NoInput(self.comp.location())),
+                Event::Halted => return Ok(output),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::str_to_vec;
+
+    #[test]
+    fn test_same_as_interpreter() {
+        let prog = str_to_vec("3,9,8,9,10,9,4,9,99,-1,8");
+        for input in [7, 8].iter() {
+            let mut comp = IntcodeComp::new(0, prog.clone());
+            comp.push_input(*input);
+            let mut fast = ThreadedComp::from_program(0, prog.clone());
+            fast.push_input(*input);
+            assert_eq!(fast.run_to_halt(), comp.run_to_halt());
+            assert_eq!(fast.comp().instrs, comp.instrs);
+            assert_eq!(fast.fallbacks, 0);
+        }
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // bumps the argument of its own `out #1` before running it
+        let prog = vec![1001,5,1,5,104,1,99];
+        let expected = IntcodeComp::new(0, prog.clone()).run_to_halt();
+        assert_eq!(expected, Ok(vec![2]));
+
+        let mut fast = ThreadedComp::from_program(0, prog.clone());
+        assert_eq!(fast.run_to_halt(), expected);
+        assert_eq!(fast.fallbacks, 0); // decoded after the write
+
+        let mut fast = ThreadedComp::from_program(0, prog);
+        fast.slot(4);
+        assert_eq!(fast.run_to_halt(), expected);
+        assert_eq!(fast.fallbacks, 1); // decoded before the write
+    }
+
+    #[test]
+    fn test_write_by_loose_instruction() {
+        // `1003` has a stray mode digit the interpreter ignores; its write
+        // into the `out #1` at 0 must still drop the decoded copy
+        let prog = str_to_vec("104,1,1003,1,1001,20,1,20,1007,20,2,21,1005,21,0,99,0,0,0,0,0,0");
+        let mut comp = IntcodeComp::new(0, prog.clone());
+        let mut fast = ThreadedComp::from_program(0, prog);
+        for input in [5, 6].iter() {
+            comp.push_input(*input);
+            fast.push_input(*input);
+        }
+        let expected = comp.run_to_halt();
+        assert_eq!(expected, Ok(vec![1, 5]));
+        assert_eq!(fast.run_to_halt(), expected);
+        assert_eq!(fast.comp().instrs, comp.instrs);
+    }
+
+    #[test]
+    fn test_set_mem_outside_code() {
+        let mut fast = ThreadedComp::from_program(0, vec![]);
+        assert_eq!(fast.set_mem(0, 1), Ok(()));
+        let mut fast = ThreadedComp::from_program(0, vec![104,1,99]);
+        fast.slot(0);
+        assert_eq!(fast.set_mem(10, 7), Ok(()));
+        assert_eq!(fast.comp().instrs.get(10), Some(7));
+        assert_eq!(fast.run_to_halt(), Ok(vec![1]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let progs = [vec![3,0,42], vec![1101,1,1,0], vec![1105,1,-1], vec![1101,1,1,-3,99],
+                     vec![109,9223372036854775807,21101,1,1,1,99]];
+        for prog in progs.iter() {
+            let mut comp = IntcodeComp::new(0, prog.clone());
+            comp.push_input(1);
+            let mut fast = ThreadedComp::from_program(0, prog.clone());
+            fast.push_input(1);
+            let err = comp.run_to_halt();
+            assert!(err.is_err());
+            assert_eq!(fast.run_to_halt(), err);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Levelled execution tracing for the Intcode VM.
+//!
+//! The console level is one of `off`, `summary`, `instruction` or `memory`.
+//! Separately, a trace file gets one tab-separated record per executed
+//! instruction:
+//!
+//! ```text
+//! <step>  <machine>  <ptr>  <rel_base>  <instruction>  <effects>
+//! 12      0          42     0           ADD [100], #1 -> [100]   [100]=5
+//! ```
+//!
+//! where the effects are memory writes (`[addr]=val`), `in=val` and
+//! `out=val`. Records carry no timing information, so two runs of the same
+//! program can be compared with `diff`.
+//!
+//! Every machine starts with its console level set by `INTCODE_TRACE`.
+//! Trace files are opt-in per machine, since a program may build many
+//! machines at once: `Tracer::from_env` also opens `INTCODE_TRACE_FILE`,
+//! with `{}` in the path replaced by the machine name, for a caller to
+//! attach with `IntcodeComp::with_tracer`.
+
+use std::env;
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::disasm::format_line;
+use crate::disasm::Line;
+use crate::memory::Memory;
+use crate::word::Word;
+use crate::Numeric;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum TraceLevel {
+    Off, // print nothing
+    Summary, // one line when a machine halts
+    Instruction, // every executed instruction
+    Memory, // every instruction and memory write
+}
+
+impl FromStr for TraceLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TraceLevel, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "off" | "0" => Ok(TraceLevel::Off),
+            "summary" | "1" => Ok(TraceLevel::Summary),
+            "instruction" | "2" => Ok(TraceLevel::Instruction),
+            "memory" | "3" => Ok(TraceLevel::Memory),
+            x => Err(format!("unknown trace level '{}'", x)),
+        }
+    }
+}
+
+/// Where trace records go; shared between clones of a machine.
+type Sink = Arc<Mutex<Box<dyn Write + Send>>>;
+
+#[derive(Clone)]
+pub struct Tracer {
+    pub level: TraceLevel, // what to print on the console
+    pub steps: u64, // instructions executed so far
+    pub outputs: u64, // values output so far
+    file: Option<Sink>, // machine-readable records
+    record: Option<String>, // record of the instruction being executed
+    halted: bool, // summary already printed
+}
+
+impl Tracer {
+    pub fn new(level: TraceLevel) -> Tracer {
+        Tracer{level, steps: 0, outputs: 0, file: None, record: None, halted: false}
+    }
+
+    /// A tracer printing at the `INTCODE_TRACE` level, with no file. The
+    /// variable is read once; a bad value is warned about and means off.
+    pub fn console() -> Tracer {
+        static LEVEL: OnceLock<TraceLevel> = OnceLock::new();
+        let level = *LEVEL.get_or_init(|| match env::var("INTCODE_TRACE") {
+            Ok(l) => l.parse().unwrap_or_else(|e| {
+                eprintln!("warning: INTCODE_TRACE: {}; tracing is off", e);
+                TraceLevel::Off
+            }),
+            Err(_) => TraceLevel::Off,
+        });
+        Tracer::new(level)
+    }
+
+    /// The tracer for machine `name` described by `INTCODE_TRACE` and
+    /// `INTCODE_TRACE_FILE`, creating the file if one is named.
+    pub fn from_env(name: usize) -> Result<Tracer, String> {
+        let mut tracer = Tracer::console();
+        if let Ok(path) = env::var("INTCODE_TRACE_FILE") {
+            let path = path.replace("{}", &name.to_string());
+            let file = File::create(&path)
+                .map_err(|e| format!("cannot create trace file {}: {}", path, e))?;
+            tracer.set_writer(Box::new(BufWriter::new(file)));
+        }
+        Ok(tracer)
+    }
+
+    /// Send trace records to `writer`.
+    pub fn set_writer(&mut self, writer: Box<dyn Write + Send>) {
+        self.file = Some(Arc::new(Mutex::new(writer)));
+    }
+
+    /// Whether instructions need decoding for the console or the file.
+    pub fn is_active(&self) -> bool {
+        self.level >= TraceLevel::Instruction || self.file.is_some()
+    }
+
+    /// Start the record for the instruction at `ptr`.
+    pub(crate) fn begin<W: Word>(&mut self, name: usize, mem: &Memory<W>, ptr: usize, rel_base: Numeric) {
+        let line = match mem.decode(ptr) {
+            Some(i) => Line::Code(i),
+            None => Line::Data{addr: ptr, values: mem.window(ptr, 1).iter().filter_map(|w| w.to_i64()).collect()},
+        };
+        let text = format_line(&line);
+        if self.level >= TraceLevel::Instruction {
+            println!("{}: {}", name, text);
+        }
+        if self.file.is_some() {
+            let text = &text[text.find(": ").map_or(0, |i| i+2)..];
+            self.record = Some(format!("{}\t{}\t{}\t{}\t{}\t", self.steps, name, ptr, rel_base, text));
+        }
+    }
+
+    /// Note a memory write by the current instruction.
+    pub(crate) fn write(&mut self, addr: usize, val: &dyn fmt::Display) {
+        if self.level >= TraceLevel::Memory {
+            println!("\t [{}] = {}", addr, val);
+        }
+        self.effect(format_args!("[{}]={}", addr, val));
+    }
+
+    pub(crate) fn input(&mut self, val: &dyn fmt::Display) {
+        self.effect(format_args!("in={}", val));
+    }
+
+    pub(crate) fn output(&mut self, val: &dyn fmt::Display) {
+        self.outputs += 1;
+        self.effect(format_args!("out={}", val));
+    }
+
+    fn effect(&mut self, args: fmt::Arguments) {
+        if let Some(r) = &mut self.record {
+            if !r.ends_with('\t') {
+                r.push(' ');
+            }
+            let _ = r.write_fmt(args);
+        }
+    }
+
+    /// Finish the current instruction, writing its record.
+    pub(crate) fn end(&mut self) {
+        self.steps += 1;
+        if let (Some(r), Some(f)) = (self.record.take(), &self.file) {
+            let _ = writeln!(f.lock().unwrap(), "{}", r);
+        }
+    }
+
+    /// Drop the current record; the instruction did not execute.
+    pub(crate) fn abort(&mut self) {
+        self.record = None;
+    }
+
+    /// Whether the halt has already been reported.
+    pub(crate) fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Report that the machine halted.
+    pub(crate) fn halt(&mut self, name: usize) {
+        self.halted = true;
+        if self.level >= TraceLevel::Summary {
+            println!("{}: halted after {} instructions, {} outputs", name, self.steps, self.outputs);
+        }
+        if let Some(f) = &self.file {
+            let _ = f.lock().unwrap().flush();
+        }
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        match &self.file {
+            Some(f) => f.lock().unwrap().flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for Tracer {
+    fn default() -> Tracer {
+        Tracer::new(TraceLevel::Off)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comp::IntcodeComp;
+
+    #[derive(Clone)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn trace(prog: Vec<Numeric>, input: Numeric) -> String {
+        let buf = Shared(Arc::new(Mutex::new(Vec::new())));
+        let mut tracer = Tracer::new(TraceLevel::Off);
+        tracer.set_writer(Box::new(buf.clone()));
+        let mut comp = IntcodeComp::new(0, prog).with_tracer(tracer);
+        comp.push_input(input);
+        comp.run_to_halt().unwrap();
+        let out = buf.0.lock().unwrap().clone();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!("memory".parse(), Ok(TraceLevel::Memory));
+        assert_eq!(" Summary".parse(), Ok(TraceLevel::Summary));
+        assert_eq!("".parse(), Ok(TraceLevel::Off));
+        assert!("loud".parse::<TraceLevel>().is_err());
+        assert!(TraceLevel::Instruction > TraceLevel::Summary);
+        assert!(!Tracer::new(TraceLevel::Summary).is_active());
+    }
+
+    #[test]
+    fn test_trace_records() {
+        let t = trace(vec![3,9,8,9,10,9,4,9,99,-1,8], 8);
+        assert_eq!(t, "\
+0\t0\t0\t0\tIN -> [9]\tin=8 [9]=8
+1\t0\t2\t0\tEQ [9], [10] -> [9]\t[9]=1
+2\t0\t6\t0\tOUT [9]\tout=1
+3\t0\t8\t0\tHALT\t
+");
+    }
+
+    #[test]
+    fn test_trace_diff() {
+        // the same program on different input diverges at the first write
+        let prog = vec![3,9,8,9,10,9,4,9,99,-1,8];
+        let a = trace(prog.clone(), 8);
+        let b = trace(prog, 7);
+        let diff: Vec<_> = a.lines().zip(b.lines()).filter(|(x, y)| x != y).collect();
+        assert_eq!(diff[0], ("0\t0\t0\t0\tIN -> [9]\tin=8 [9]=8", "0\t0\t0\t0\tIN -> [9]\tin=7 [9]=7"));
+    }
+}
@@ -0,0 +1,119 @@
+//! Word types the VM can run on.
+//!
+//! `IntcodeComp<W>` computes in any `Word`, picked when the machine is
+//! built. Arithmetic is always checked, so a result that does not fit is an
+//! `IntcodeError::Overflow` rather than a silent wrap:
+//!
+//! - `i64` (`Numeric`), the default, is enough for every 2019 puzzle.
+//! - `i128` gives headroom for programs that square large numbers.
+//! - `BigInt`, with the `num-bigint` feature, never overflows.
+//!
+//! Addresses, opcodes and the relative base are always `Numeric`; a word
+//! used as one of those has to fit in an `i64`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Numeric;
+
+pub trait Word: Clone + Eq + Ord + fmt::Debug + fmt::Display + FromStr + Send + Sync + 'static {
+    fn from_i64(val: Numeric) -> Self;
+    /// The value as a `Numeric`, or `None` if it does not fit.
+    fn to_i64(&self) -> Option<Numeric>;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        self.to_i64() == Some(0)
+    }
+}
+
+impl Word for i64 {
+    fn from_i64(val: Numeric) -> i64 {
+        val
+    }
+
+    fn to_i64(&self) -> Option<Numeric> {
+        Some(*self)
+    }
+
+    fn checked_add(&self, other: &i64) -> Option<i64> {
+        i64::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &i64) -> Option<i64> {
+        i64::checked_mul(*self, *other)
+    }
+}
+
+impl Word for i128 {
+    fn from_i64(val: Numeric) -> i128 {
+        val as i128
+    }
+
+    fn to_i64(&self) -> Option<Numeric> {
+        use std::convert::TryFrom;
+        Numeric::try_from(*self).ok()
+    }
+
+    fn checked_add(&self, other: &i128) -> Option<i128> {
+        i128::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &i128) -> Option<i128> {
+        i128::checked_mul(*self, *other)
+    }
+}
+
+#[cfg(feature = "num-bigint")]
+impl Word for num_bigint::BigInt {
+    fn from_i64(val: Numeric) -> num_bigint::BigInt {
+        val.into()
+    }
+
+    fn to_i64(&self) -> Option<Numeric> {
+        use std::convert::TryFrom;
+        Numeric::try_from(self).ok()
+    }
+
+    fn checked_add(&self, other: &num_bigint::BigInt) -> Option<num_bigint::BigInt> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &num_bigint::BigInt) -> Option<num_bigint::BigInt> {
+        Some(self * other)
+    }
+}
+
+/// Parse a comma-separated Intcode program into any word type.
+pub fn str_to_words<W: Word>(input: &str) -> Vec<W> {
+    input.trim().split(',').map(|e| match e.trim().parse() {
+        Ok(w) => w,
+        Err(_) => panic!("not an Intcode word: '{}'", e.trim()),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(Word::checked_mul(&i64::MAX, &2), None);
+        assert_eq!(Word::checked_mul(&(i64::MAX as i128), &2), Some(i64::MAX as i128 * 2));
+        assert_eq!((i64::MAX as i128 * 2).to_i64(), None);
+        assert!(<i128 as Word>::from_i64(0).is_zero());
+        assert_eq!(str_to_words::<i128>("1, -2,99"), vec![1, -2, 99]);
+    }
+
+    #[cfg(feature = "num-bigint")]
+    #[test]
+    fn test_bigint() {
+        use num_bigint::BigInt;
+        let big = BigInt::from_i64(i64::MAX);
+        let square = big.checked_mul(&big).unwrap();
+        assert_eq!(square.to_string(), "85070591730234615847396907784232501249");
+        assert_eq!(square.to_i64(), None);
+        assert_eq!(str_to_words::<BigInt>("99")[0].to_i64(), Some(99));
+    }
+}
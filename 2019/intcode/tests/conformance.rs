@@ -0,0 +1,242 @@
+//! Conformance suite shared by every 2019 Intcode day.
+//!
+//! These are the examples from the puzzle texts of days 2, 5, 7 and 9 plus
+//! the full day 5 and day 9 programs, so any interpreter change is checked
+//! against every opcode and parameter mode.
+
+use intcode::asm::assemble;
+use intcode::cfg::Cfg;
+use intcode::disasm::listing;
+use intcode::word::str_to_words;
+use intcode::{str_to_vec, Event, IntcodeComp, IntcodeError, Memory, Numeric, ThreadedComp, Word};
+
+const DAY4_COMPARE: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+const DAY5: &str = "3,225,1,225,6,6,1100,1,238,225,104,0,2,171,209,224,1001,224,-1040,224,4,224,102,8,223,223,1001,224,4,224,1,223,224,223,102,65,102,224,101,-3575,224,224,4,224,102,8,223,223,101,2,224,224,1,223,224,223,1102,9,82,224,1001,224,-738,224,4,224,102,8,223,223,1001,224,2,224,1,223,224,223,1101,52,13,224,1001,224,-65,224,4,224,1002,223,8,223,1001,224,6,224,1,223,224,223,1102,82,55,225,1001,213,67,224,1001,224,-126,224,4,224,102,8,223,223,1001,224,7,224,1,223,224,223,1,217,202,224,1001,224,-68,224,4,224,1002,223,8,223,1001,224,1,224,1,224,223,223,1002,176,17,224,101,-595,224,224,4,224,102,8,223,223,101,2,224,224,1,224,223,223,1102,20,92,225,1102,80,35,225,101,21,205,224,1001,224,-84,224,4,224,1002,223,8,223,1001,224,1,224,1,224,223,223,1101,91,45,225,1102,63,5,225,1101,52,58,225,1102,59,63,225,1101,23,14,225,4,223,99,0,0,0,677,0,0,0,0,0,0,0,0,0,0,0,1105,0,99999,1105,227,247,1105,1,99999,1005,227,99999,1005,0,256,1105,1,99999,1106,227,99999,1106,0,265,1105,1,99999,1006,0,99999,1006,227,274,1105,1,99999,1105,1,280,1105,1,99999,1,225,225,225,1101,294,0,0,105,1,0,1105,1,99999,1106,0,300,1105,1,99999,1,225,225,225,1101,314,0,0,106,0,0,1105,1,99999,1008,677,677,224,1002,223,2,223,1006,224,329,101,1,223,223,1108,226,677,224,1002,223,2,223,1006,224,344,101,1,223,223,7,677,226,224,102,2,223,223,1006,224,359,1001,223,1,223,8,677,226,224,102,2,223,223,1005,224,374,1001,223,1,223,1107,677,226,224,102,2,223,223,1006,224,389,1001,223,1,223,1008,226,226,224,1002,223,2,223,1005,224,404,1001,223,1,223,7,226,677,224,102,2,223,223,1005,224,419,1001,223,1,223,1007,677,677,224,102,2,223,223,1006,224,434,1001,223,1,223,107,226,226,224,1002,223,2,223,1005,224,449,1001,223,1,223,1008,677,226,224,102,2,223,223,1006,224,464,1001,223,1,223,1007,677,226,224,1002,223,2,223,1005,224,479,1001,223,1,223,108,677,677,224,1002,223,2,223,1006,224,494,1001,223,1,223,108,226,226,224,1002,223,2,223,1006,224,509,101,1,223,223,8,226,677,224,102,2,223,223,1006,224,524,101,1,223,223,107,677,226,224,1002,223,2,223,1005,224,539,1001,223,1,223,8,226,226,224,102,2,223,223,1005,224,554,101,1,223,223,1108,677,226,224,102,2,223,223,1006,224,569,101,1,223,223,108,677,226,224,102,2,223,223,1006,224,584,1001,223,1,223,7,677,677,224,1002,223,2,223,1005,224,599,101,1,223,223,1007,226,226,224,102,2,223,223,1005,224,614,1001,223,1,223,1107,226,677,224,102,2,223,223,1006,224,629,101,1,223,223,1107,226,226,224,102,2,223,223,1005,224,644,1001,223,1,223,1108,677,677,224,1002,223,2,223,1005,224,659,101,1,223,223,107,677,677,224,1002,223,2,223,1006,224,674,1001,223,1,223,4,223,99,226";
+const BOOST: &str = "1102,34463338,34463338,63,1007,63,34463338,63,1005,63,53,1101,0,3,1000,109,988,209,12,9,1000,209,6,209,3,203,0,1008,1000,1,63,1005,63,65,1008,1000,2,63,1005,63,904,1008,1000,0,63,1005,63,58,4,25,104,0,99,4,0,104,0,99,4,17,104,0,99,0,0,1101,0,396,1029,1101,0,356,1023,1101,401,0,1028,1101,24,0,1008,1101,33,0,1019,1101,35,0,1010,1102,359,1,1022,1102,32,1,1001,1101,37,0,1004,1101,0,31,1009,1101,0,30,1003,1101,28,0,1002,1102,1,36,1014,1102,20,1,1012,1101,21,0,1000,1101,0,22,1015,1102,23,1,1013,1102,1,1,1021,1102,1,39,1007,1102,26,1,1017,1101,0,38,1016,1101,0,437,1024,1102,432,1,1025,1101,0,421,1026,1101,0,29,1005,1101,27,0,1011,1102,1,0,1020,1101,0,25,1018,1101,0,414,1027,1102,34,1,1006,109,6,2108,33,-3,63,1005,63,201,1001,64,1,64,1105,1,203,4,187,1002,64,2,64,109,14,21108,40,40,-6,1005,1014,221,4,209,1105,1,225,1001,64,1,64,1002,64,2,64,109,-21,2102,1,3,63,1008,63,28,63,1005,63,251,4,231,1001,64,1,64,1106,0,251,1002,64,2,64,109,12,2101,0,-3,63,1008,63,21,63,1005,63,275,1001,64,1,64,1105,1,277,4,257,1002,64,2,64,109,-10,1207,1,27,63,1005,63,293,1105,1,299,4,283,1001,64,1,64,1002,64,2,64,109,9,21108,41,42,3,1005,1013,315,1105,1,321,4,305,1001,64,1,64,1002,64,2,64,109,-12,1202,6,1,63,1008,63,37,63,1005,63,347,4,327,1001,64,1,64,1105,1,347,1002,64,2,64,109,29,2105,1,-4,1105,1,365,4,353,1001,64,1,64,1002,64,2,64,109,-17,2108,32,-9,63,1005,63,387,4,371,1001,64,1,64,1105,1,387,1002,64,2,64,109,17,2106,0,1,4,393,1105,1,405,1001,64,1,64,1002,64,2,64,109,1,2106,0,-1,1001,64,1,64,1106,0,423,4,411,1002,64,2,64,109,-13,2105,1,9,4,429,1106,0,441,1001,64,1,64,1002,64,2,64,109,3,21107,42,41,-1,1005,1017,461,1001,64,1,64,1106,0,463,4,447,1002,64,2,64,109,-4,21107,43,44,1,1005,1015,481,4,469,1106,0,485,1001,64,1,64,1002,64,2,64,109,-6,21101,44,0,6,1008,1014,47,63,1005,63,505,1106,0,511,4,491,1001,64,1,64,1002,64,2,64,109,-6,1208,-1,32,63,1005,63,529,4,517,1105,1,533,1001,64,1,64,1002,64,2,64,109,11,1205,7,545,1106,0,551,4,539,1001,64,1,64,1002,64,2,64,109,11,21102,45,1,-7,1008,1017,48,63,1005,63,575,1001,64,1,64,1106,0,577,4,557,1002,64,2,64,109,-8,1206,5,593,1001,64,1,64,1105,1,595,4,583,1002,64,2,64,109,7,1206,-3,609,4,601,1106,0,613,1001,64,1,64,1002,64,2,64,109,-10,2101,0,-6,63,1008,63,39,63,1005,63,635,4,619,1106,0,639,1001,64,1,64,1002,64,2,64,109,-9,1208,0,39,63,1005,63,655,1106,0,661,4,645,1001,64,1,64,1002,64,2,64,109,4,2107,25,0,63,1005,63,681,1001,64,1,64,1105,1,683,4,667,1002,64,2,64,109,-5,2107,31,-2,63,1005,63,701,4,689,1106,0,705,1001,64,1,64,1002,64,2,64,109,19,1205,-1,719,4,711,1105,1,723,1001,64,1,64,1002,64,2,64,109,-17,1201,3,0,63,1008,63,24,63,1005,63,745,4,729,1106,0,749,1001,64,1,64,1002,64,2,64,109,13,21102,46,1,-3,1008,1015,46,63,1005,63,771,4,755,1105,1,775,1001,64,1,64,1002,64,2,64,109,-13,1207,4,32,63,1005,63,793,4,781,1106,0,797,1001,64,1,64,1002,64,2,64,109,7,2102,1,-9,63,1008,63,27,63,1005,63,821,1001,64,1,64,1105,1,823,4,803,1002,64,2,64,109,-18,1201,8,0,63,1008,63,25,63,1005,63,847,1001,64,1,64,1106,0,849,4,829,1002,64,2,64,109,23,21101,47,0,2,1008,1019,47,63,1005,63,871,4,855,1106,0,875,1001,64,1,64,1002,64,2,64,109,-22,1202,5,1,63,1008,63,19,63,1005,63,899,1001,64,1,64,1106,0,901,4,881,4,64,99,21102,27,1,1,21102,1,915,0,1105,1,922,21201,1,25165,1,204,1,99,109,3,1207,-2,3,63,1005,63,964,21201,-2,-1,1,21102,942,1,0,1105,1,922,22102,1,1,-1,21201,-2,-3,1,21101,0,957,0,1105,1,922,22201,1,-1,-2,1106,0,968,21201,-2,0,-2,109,-3,2105,1,0";
+
+fn run_with_input(input: &str, val: Numeric) -> Option<Numeric> {
+    let mut comp = IntcodeComp::new(0, str_to_vec(input));
+    comp.push_input(val);
+    comp.run_to_halt().unwrap().last().copied()
+}
+
+#[test]
+fn test_run_day2() {
+    let v = str_to_vec("1,9,10,3,2,3,11,0,99,30,40,50");
+    let mut comp = IntcodeComp::new(0, v);
+    assert_eq!(comp.run_to_halt().unwrap(), vec![]);
+    assert_eq!(comp.instrs[0], 3500);
+}
+
+#[test]
+fn test_run_day4() {
+    assert_eq!(run_with_input(DAY4_COMPARE, 7), Some(999));
+}
+
+#[test]
+fn test_run_day4a() {
+    assert_eq!(run_with_input(DAY4_COMPARE, 8), Some(1000));
+}
+
+#[test]
+fn test_run_day4b() {
+    assert_eq!(run_with_input(DAY4_COMPARE, 9), Some(1001));
+}
+
+#[test]
+fn test_run_day5_1a() {
+    assert_eq!(run_with_input("3,9,8,9,10,9,4,9,99,-1,8", 8), Some(1));
+}
+
+#[test]
+fn test_run_day5_1b() {
+    assert_eq!(run_with_input("3,9,8,9,10,9,4,9,99,-1,8", 16), Some(0));
+}
+
+#[test]
+fn test_run_day5_2a() {
+    assert_eq!(run_with_input("3,9,7,9,10,9,4,9,99,-1,8", 5), Some(1));
+}
+
+#[test]
+fn test_run_day5_2b() {
+    assert_eq!(run_with_input("3,9,7,9,10,9,4,9,99,-1,8", 8), Some(0));
+}
+
+#[test]
+fn test_run_day5_immediate() {
+    assert_eq!(run_with_input("3,3,1107,-1,8,3,4,3,99", 7), Some(1));
+    assert_eq!(run_with_input("3,3,1108,-1,8,3,4,3,99", 7), Some(0));
+}
+
+#[test]
+fn test_run_day5_jumps() {
+    assert_eq!(run_with_input("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", 0), Some(0));
+    assert_eq!(run_with_input("3,3,1105,-1,9,1101,0,0,12,4,12,99,1", 5), Some(1));
+}
+
+#[test]
+fn test_run_day5a() {
+    // every diagnostic check passes with a 0 before the final code
+    let mut comp = IntcodeComp::new(0, str_to_vec(DAY5));
+    comp.push_input(1);
+    let output = comp.run_to_halt().unwrap();
+    let (code, checks) = output.split_last().unwrap();
+    assert!(checks.iter().all(|x| *x == 0));
+    assert_eq!(*code, 9006673);
+}
+
+#[test]
+fn test_run_day5b() {
+    assert_eq!(run_with_input(DAY5, 5), Some(3629692));
+}
+
+#[test]
+fn test_run_day7_amplifier() {
+    // a single amplifier reads its phase, then waits for the input signal
+    let mut comp = IntcodeComp::new(0, str_to_vec("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0"));
+    comp.push_input(4);
+    assert_eq!(comp.run_until_event(), Ok(Event::NeedsInput));
+    comp.push_input(3);
+    assert_eq!(comp.run_until_event(), Ok(Event::Output(34)));
+    assert_eq!(comp.run_until_event(), Ok(Event::Halted));
+}
+
+#[test]
+fn test_run_day9a_1() {
+    let input = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    let v = str_to_vec(input);
+    let mut comp = IntcodeComp::new(0, v.clone());
+    assert_eq!(comp.run_to_halt().unwrap(), v);
+}
+
+#[test]
+fn test_run_day9a_2() {
+    let v = str_to_vec("1102,34915192,34915192,7,4,7,99,0");
+    let out = IntcodeComp::new(0, v).run_to_halt().unwrap();
+    assert_eq!(out[0].to_string().len(), 16);
+}
+
+#[test]
+fn test_run_day9a_3() {
+    let v = str_to_vec("104,1125899906842624,99");
+    let out = IntcodeComp::new(0, v).run_to_halt().unwrap();
+    assert_eq!(out, vec![1125899906842624]);
+}
+
+#[test]
+fn test_run_day9a() {
+    assert_eq!(run_with_input(BOOST, 1), Some(3497884671));
+}
+
+#[test]
+fn test_run_day9b() {
+    assert_eq!(run_with_input(BOOST, 2), Some(46470));
+}
+
+#[test]
+fn test_relative_base_call() {
+    // a subroutine called through the stack doubles its argument
+    let prog = assemble("
+                arb #stack
+                in -> [rb+1]
+                add #0, #ret, [rb+0]
+                jt #1, #double
+        ret:    out [rb+1]
+                halt
+        double: add [rb+1], [rb+1] -> [rb+1]
+                jt #1, [rb+0]
+        stack:  zeros 4
+    ").unwrap();
+    let mut comp = IntcodeComp::new(0, prog);
+    comp.push_input(21);
+    assert_eq!(comp.run_to_halt().unwrap(), vec![42]);
+}
+
+#[test]
+fn test_negative_relative_offset() {
+    let prog = assemble("
+                arb #cells+2
+                out [rb-2]
+                out [rb-1]
+                halt
+        cells:  data 7, -8
+    ").unwrap();
+    assert_eq!(IntcodeComp::new(0, prog).run_to_halt().unwrap(), vec![7, -8]);
+}
+
+#[test]
+fn test_assembler_round_trip() {
+    for prog in [DAY5, BOOST].iter() {
+        let v = str_to_vec(prog);
+        assert_eq!(assemble(&listing(&v)).unwrap(), v);
+    }
+}
+
+#[test]
+fn test_day9b_paged() {
+    let mut comp = IntcodeComp::with_memory(0, Memory::paged(&str_to_vec(BOOST)).with_limit(4096));
+    comp.push_input(2);
+    assert_eq!(comp.run_to_halt().unwrap(), vec![46470]);
+}
+
+#[test]
+fn test_threaded_matches_interpreter() {
+    for (prog, input) in [(DAY5, 1), (DAY5, 5), (BOOST, 1), (BOOST, 2)].iter() {
+        let mut comp = ThreadedComp::from_program(0, str_to_vec(prog));
+        comp.push_input(*input);
+        assert_eq!(comp.run_to_halt().unwrap().last().copied(), run_with_input(prog, *input));
+    }
+}
+
+#[test]
+fn test_boost_cfg() {
+    // the recursive function at 922 opens a frame of 3 and calls itself twice
+    let cfg = Cfg::new(&str_to_vec(BOOST));
+    assert_eq!(cfg.functions().get(&922), Some(&Some(3)));
+    let sites: Vec<usize> = cfg.calls.iter().filter(|c| c.target == 922).map(|c| c.site).collect();
+    assert_eq!(sites, vec![912, 939, 954]);
+    assert!(cfg.blocks.values().any(|b| b.returns && b.end() == 973));
+}
+
+/// The day9 examples and BOOST, with outputs compared as text.
+fn day9_in<W: Word>() {
+    let run = |prog: &str, input: Option<Numeric>| {
+        let mut comp = IntcodeComp::<W>::from_words(0, str_to_words(prog));
+        if let Some(i) = input {
+            comp.push_input(W::from_i64(i));
+        }
+        let out: Vec<String> = comp.run_to_halt().unwrap().iter().map(|w| w.to_string()).collect();
+        out.join(",")
+    };
+    let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+    assert_eq!(run(quine, None), quine);
+    assert_eq!(run("1102,34915192,34915192,7,4,7,99,0", None), "1219070632396864");
+    assert_eq!(run("104,1125899906842624,99", None), "1125899906842624");
+    assert_eq!(run(BOOST, Some(1)), "3497884671");
+    assert_eq!(run(BOOST, Some(2)), "46470");
+}
+
+#[test]
+fn test_day9_word_types() {
+    day9_in::<i64>();
+    day9_in::<i128>();
+}
+
+#[cfg(feature = "num-bigint")]
+#[test]
+fn test_day9_bigint() {
+    day9_in::<num_bigint::BigInt>();
+}
+
+#[test]
+fn test_overflow() {
+    // squares 2^62, which needs more than 64 bits
+    let prog = "1102,4611686018427387904,4611686018427387904,7,4,7,99,0";
+    let err = IntcodeComp::new(0, str_to_vec(prog)).run_to_halt().unwrap_err();
+    assert!(matches!(err, IntcodeError::Overflow(at) if at.ptr == 0));
+    assert_eq!(ThreadedComp::from_program(0, str_to_vec(prog)).run_to_halt(), Err(err));
+
+    let out = IntcodeComp::<i128>::from_words(0, str_to_words(prog)).run_to_halt().unwrap();
+    assert_eq!(out, vec![1 << 124]);
+}